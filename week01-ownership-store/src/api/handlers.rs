@@ -1,5 +1,6 @@
 use crate::api::state::AppState;
-use crate::error::ApiError;
+use crate::error::{ApiError, ErrorBody};
+use crate::http::extract::ApiJson;
 use crate::model::KvPair;
 use crate::storage::file;
 use axum::Json;
@@ -7,18 +8,48 @@ use axum::extract::{Path, State};
 use serde_json::Value;
 use std::collections::HashMap;
 
+async fn save(state: &AppState, map: &HashMap<String, Value>) -> Result<(), ApiError> {
+    let result = file::save_to_file(&state.data_file, map, false).await;
+    state.metrics.observe_save(result.is_ok());
+    result
+}
+
+#[utoipa::path(
+    post,
+    path = "/kv/{key}",
+    tag = "kv",
+    params(("key" = String, Path, description = "Key to store the value under")),
+    request_body(content = Value, description = "Any JSON value"),
+    responses(
+        (status = 200, body = KvPair, description = "The stored pair"),
+        (status = 400, body = ErrorBody, description = "Malformed JSON body"),
+        (status = 413, body = ErrorBody, description = "Body over the configured limit"),
+        (status = 415, body = ErrorBody, description = "Body is not `application/json`"),
+        (status = 500, body = ErrorBody, description = "Saving the data file failed"),
+    )
+)]
 pub async fn put_key(
     State(state): State<AppState>,
     Path(key): Path<String>,
-    Json(value): Json<Value>,
+    ApiJson(value): ApiJson<Value>,
 ) -> Result<Json<KvPair>, ApiError> {
     let mut guard = state.store.write().await;
     guard.insert(key.clone(), value.clone());
-    file::save_to_file(&state.data_file, &guard).await?;
+    save(&state, &guard).await?;
 
     Ok(Json(KvPair { key, value }))
 }
 
+#[utoipa::path(
+    get,
+    path = "/kv/{key}",
+    tag = "kv",
+    params(("key" = String, Path)),
+    responses(
+        (status = 200, body = KvPair, description = "The pair"),
+        (status = 404, body = ErrorBody, description = "No such key"),
+    )
+)]
 pub async fn get_key(
     State(state): State<AppState>,
     Path(key): Path<String>,
@@ -32,6 +63,12 @@ pub async fn get_key(
     }))
 }
 
+#[utoipa::path(
+    get,
+    path = "/kv",
+    tag = "kv",
+    responses((status = 200, body = HashMap<String, Value>, description = "Every stored key and value"))
+)]
 pub async fn get_all(
     State(state): State<AppState>,
 ) -> Result<Json<HashMap<String, Value>>, ApiError> {
@@ -39,13 +76,24 @@ pub async fn get_all(
     Ok(Json(guard.clone()))
 }
 
+#[utoipa::path(
+    delete,
+    path = "/kv/{key}",
+    tag = "kv",
+    params(("key" = String, Path)),
+    responses(
+        (status = 200, body = KvPair, description = "The removed pair"),
+        (status = 404, body = ErrorBody, description = "No such key"),
+        (status = 500, body = ErrorBody, description = "Saving the data file failed"),
+    )
+)]
 pub async fn delete_key(
     State(state): State<AppState>,
     Path(key): Path<String>,
 ) -> Result<Json<KvPair>, ApiError> {
     let mut guard = state.store.write().await;
     let value = guard.remove(&key).ok_or(ApiError::NotFound)?;
-    file::save_to_file(&state.data_file, &guard).await?;
+    save(&state, &guard).await?;
 
     Ok(Json(KvPair { key, value }))
 }
@@ -1,14 +1,21 @@
 use crate::api::handlers::*;
 use axum::Router;
-use axum::routing::{get, post};
+use tower_http::decompression::RequestDecompressionLayer;
+use utoipa_axum::{router::OpenApiRouter, routes};
 
 use crate::api::state::AppState;
 pub mod handlers;
 pub mod state;
 
 pub fn router(state: AppState) -> Router {
-    Router::new()
-        .route("/kv", get(get_all))
-        .route("/kv/{key}", post(put_key).get(get_key).delete(delete_key))
-        .with_state(state)
+    documented().with_state(state).into()
+}
+
+/// The `/kv` routes together with their OpenAPI operations. Request bodies may
+/// be gzip, brotli or zstd compressed; body limits apply after decompression.
+pub fn documented() -> OpenApiRouter<AppState> {
+    OpenApiRouter::new()
+        .routes(routes!(get_all))
+        .routes(routes!(put_key, get_key, delete_key))
+        .layer(RequestDecompressionLayer::new())
 }
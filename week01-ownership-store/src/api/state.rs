@@ -1,7 +1,10 @@
+use crate::metrics::Metrics;
 use crate::storage::Store;
+use std::sync::Arc;
 
 #[derive(Clone)]
 pub struct AppState {
     pub store: Store,
     pub data_file: String,
+    pub metrics: Arc<Metrics>,
 }
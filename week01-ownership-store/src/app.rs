@@ -0,0 +1,173 @@
+use axum::Router;
+use axum::extract::{DefaultBodyLimit, Request};
+use axum::http::Method;
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+use tower_http::compression::CompressionLayer;
+use tower_http::compression::predicate::{NotForContentType, Predicate, SizeAbove};
+use utoipa_axum::router::OpenApiRouter;
+
+use crate::api::{self, state::AppState};
+use crate::error::{self, ApiError, ErrorFormat};
+use crate::health::{self, Readiness};
+use crate::http::{self, Limits};
+use crate::metrics;
+use crate::openapi;
+use crate::telemetry;
+
+/// Smallest response body compressed by default.
+pub const DEFAULT_COMPRESS_MIN_SIZE: u16 = 1024;
+
+/// Negotiated gzip, brotli or zstd compression of response bodies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Compression {
+    pub enabled: bool,
+    /// Bodies smaller than this many bytes are sent as they are, since
+    /// compressing them saves little. Streamed bodies of unknown length are
+    /// always compressed, except for NDJSON lines.
+    pub min_size: u16,
+}
+
+impl Default for Compression {
+    fn default() -> Self {
+        Compression {
+            enabled: true,
+            min_size: DEFAULT_COMPRESS_MIN_SIZE,
+        }
+    }
+}
+
+/// How the text API and the KV API are mounted in one server.
+#[derive(Debug, Clone, Default)]
+pub struct AppConfig {
+    /// Mounts the `/v1` routes under this path, e.g. `/text` serves
+    /// `/text/v1/stats`.
+    pub text_prefix: Option<PathPrefix>,
+    /// Mounts the `/kv` routes under this path.
+    pub kv_prefix: Option<PathPrefix>,
+    pub limits: Limits,
+    /// Replaces every body limit, of the `/v1` and the `/kv` routes alike.
+    pub max_body: Option<usize>,
+    /// Rejects `/kv` writes with `403 READ_ONLY`.
+    pub read_only: bool,
+    pub error_format: ErrorFormat,
+    pub compression: Compression,
+    /// Why the data file could not be loaded; `/readyz` reports it.
+    pub load_error: Option<String>,
+}
+
+/// Checks that a prefix can be nested: it must start with `/`, must not end
+/// with one and must not be the root.
+pub fn validate_prefix(prefix: &str) -> Result<(), String> {
+    if !prefix.starts_with('/') {
+        return Err(format!("path prefix must start with `/`: {prefix}"));
+    }
+    if prefix.ends_with('/') {
+        return Err(format!("path prefix must not end with `/`: {prefix}"));
+    }
+    if prefix.contains(['{', '}', '*']) {
+        return Err(format!("path prefix must not contain parameters: {prefix}"));
+    }
+    Ok(())
+}
+
+/// A path the routes can be nested under, checked by [`validate_prefix`] when
+/// it is parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathPrefix(String);
+
+impl PathPrefix {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl FromStr for PathPrefix {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        validate_prefix(s)?;
+        Ok(PathPrefix(s.to_string()))
+    }
+}
+
+impl fmt::Display for PathPrefix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+async fn reject_writes(req: Request, next: Next) -> Response {
+    if matches!(*req.method(), Method::GET | Method::HEAD | Method::OPTIONS) {
+        next.run(req).await
+    } else {
+        ApiError::ReadOnly.into_response()
+    }
+}
+
+fn mount(router: OpenApiRouter, prefix: Option<&PathPrefix>) -> OpenApiRouter {
+    match prefix {
+        Some(prefix) => OpenApiRouter::new().nest(prefix.as_str(), router),
+        None => router,
+    }
+}
+
+/// The whole server: both APIs with their prefixes and error format, plus
+/// `/openapi.json` and `/docs` describing the routes as mounted, `/metrics`,
+/// `/healthz` and `/readyz`. Every request gets an `X-Request-Id` and a
+/// tracing span and is counted in the metrics, and responses are compressed
+/// if the client accepts it.
+pub fn router(state: AppState, config: &AppConfig) -> Router {
+    let limits = match config.max_body {
+        Some(bytes) => config.limits.with_body_limit(bytes),
+        None => config.limits,
+    };
+    let text = mount(http::documented(limits), config.text_prefix.as_ref());
+
+    let mut kv = api::documented().with_state(state.clone());
+    if let Some(bytes) = config.max_body {
+        kv = kv.layer(DefaultBodyLimit::max(bytes));
+    }
+    if config.read_only {
+        kv = kv.route_layer(middleware::from_fn(reject_writes));
+    }
+    let kv = mount(kv, config.kv_prefix.as_ref());
+
+    let (routes, doc) = OpenApiRouter::with_openapi(openapi::base())
+        .merge(text)
+        .merge(kv)
+        .split_for_parts();
+
+    let app_metrics = Arc::clone(&state.metrics);
+    let mut app = error::with_format(routes, config.error_format)
+        .merge(openapi::serve(doc))
+        .merge(metrics::router(state.clone()))
+        .merge(health::router(
+            state,
+            Readiness {
+                load_error: config.load_error.clone(),
+                read_only: config.read_only,
+                ..Readiness::default()
+            },
+        ));
+    if config.compression.enabled {
+        // NDJSON lines are streamed as they are found; compressing would hold
+        // them back until the encoder's buffer fills
+        let when = SizeAbove::new(config.compression.min_size)
+            .and(NotForContentType::GRPC)
+            .and(NotForContentType::IMAGES)
+            .and(NotForContentType::SSE)
+            .and(NotForContentType::const_new(http::handlers::NDJSON));
+        app = app.layer(CompressionLayer::new().compress_when(when));
+    }
+
+    app.layer(middleware::from_fn_with_state(
+        app_metrics,
+        metrics::track_requests,
+    ))
+    .layer(middleware::from_fn(telemetry::trace_requests))
+    .layer(middleware::from_fn(telemetry::request_id))
+}
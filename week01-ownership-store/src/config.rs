@@ -0,0 +1,621 @@
+use clap::Parser;
+use serde::Deserialize;
+use std::fmt;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+use thiserror::Error;
+
+use crate::app::{AppConfig, Compression, DEFAULT_COMPRESS_MIN_SIZE, PathPrefix};
+use crate::error::ErrorFormat;
+use crate::server::{DEFAULT_DRAIN_TIMEOUT, UnixSocket};
+use crate::storage::recovery::LoadErrorPolicy;
+use crate::telemetry::LogFormat;
+use crate::tls::TlsFiles;
+
+pub const DEFAULT_LISTEN: &str = "127.0.0.1:3000";
+pub const DEFAULT_DATA_FILE: &str = "data.json";
+pub const DEFAULT_UNIX_SOCKET_MODE: u32 = 0o660;
+
+/// Command-line flags of the server. Every flag can also be set with its
+/// `STORE_*` environment variable, and most in the `--config` file; flags and
+/// variables win over the file.
+#[derive(Parser, Debug, Default)]
+#[command(name = "week01-ownership-store", version)]
+pub struct Cli {
+    /// Read settings from this TOML file.
+    #[arg(long, env = "STORE_CONFIG", value_name = "PATH")]
+    pub config: Option<PathBuf>,
+    /// Address to listen on [default: 127.0.0.1:3000].
+    #[arg(long, env = "STORE_LISTEN", value_name = "ADDR")]
+    pub listen: Option<String>,
+    /// JSON file the key-value store is loaded from and saved to [default: data.json].
+    #[arg(long, env = "STORE_DATA_FILE", value_name = "PATH")]
+    pub data_file: Option<PathBuf>,
+    /// Body limit for every route, in bytes or with a K, M or G suffix.
+    #[arg(long, env = "STORE_MAX_BODY", value_name = "SIZE")]
+    pub max_body: Option<String>,
+    /// `error`, `warn`, `info` (default), `debug` or `trace`.
+    #[arg(long, env = "STORE_LOG_LEVEL", value_name = "LEVEL")]
+    pub log_level: Option<String>,
+    /// `pretty` (default) or `json`.
+    #[arg(long, env = "STORE_LOG_FORMAT", value_name = "FORMAT")]
+    pub log_format: Option<String>,
+    /// Reject writes to the key-value store; `--read-only=false` overrides
+    /// `read_only = true` in the config file.
+    #[arg(
+        long,
+        env = "STORE_READ_ONLY",
+        value_name = "BOOL",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true",
+        value_parser = clap::builder::BoolishValueParser::new()
+    )]
+    pub read_only: Option<bool>,
+    /// Never compress responses.
+    #[arg(long, env = "STORE_NO_COMPRESSION")]
+    pub no_compression: bool,
+    /// Smallest response compressed, in bytes or with a K suffix, up to 65535 [default: 1K].
+    #[arg(long, env = "STORE_COMPRESS_MIN_SIZE", value_name = "SIZE")]
+    pub compress_min_size: Option<String>,
+    /// If the data file cannot be loaded: `fail` (default), `read-only`,
+    /// `quarantine` or `backup`.
+    #[arg(long, env = "STORE_ON_LOAD_ERROR", value_name = "POLICY")]
+    pub on_load_error: Option<String>,
+    /// `envelope` (default), `problem` or `legacy`.
+    #[arg(long, env = "STORE_ERROR_FORMAT", value_name = "FORMAT")]
+    pub error_format: Option<String>,
+    /// Mount the `/v1` text API under this path.
+    #[arg(long, env = "STORE_TEXT_PREFIX", value_name = "PATH")]
+    pub text_prefix: Option<String>,
+    /// Mount the `/kv` store under this path.
+    #[arg(long, env = "STORE_KV_PREFIX", value_name = "PATH")]
+    pub kv_prefix: Option<String>,
+    /// Seconds in-flight requests get to finish on shutdown [default: 30].
+    #[arg(long, env = "STORE_SHUTDOWN_TIMEOUT", value_name = "SECS")]
+    pub shutdown_timeout: Option<String>,
+    /// Serve HTTPS on `--listen` with this PEM certificate chain; needs
+    /// `--tls-key`. SIGHUP reloads both files.
+    #[arg(long, env = "STORE_TLS_CERT", value_name = "PATH")]
+    pub tls_cert: Option<PathBuf>,
+    /// PEM private key of `--tls-cert`.
+    #[arg(long, env = "STORE_TLS_KEY", value_name = "PATH")]
+    pub tls_key: Option<PathBuf>,
+    /// Also listen on this Unix domain socket.
+    #[arg(long, env = "STORE_UNIX_SOCKET", value_name = "PATH")]
+    pub unix_socket: Option<PathBuf>,
+    /// Permissions of the socket file, in octal [default: 660].
+    #[arg(long, env = "STORE_UNIX_SOCKET_MODE", value_name = "MODE")]
+    pub unix_socket_mode: Option<String>,
+}
+
+/// Contents of the `--config` file. Unset values fall through to the
+/// built-in defaults.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FileConfig {
+    pub listen: Option<String>,
+    pub data_file: Option<PathBuf>,
+    pub max_body: Option<Size>,
+    pub log_level: Option<String>,
+    pub log_format: Option<String>,
+    pub read_only: Option<bool>,
+    pub compression: Option<bool>,
+    pub compress_min_size: Option<Size>,
+    pub on_load_error: Option<String>,
+    pub error_format: Option<String>,
+    pub text_prefix: Option<String>,
+    pub kv_prefix: Option<String>,
+    pub shutdown_timeout: Option<u64>,
+    pub tls_cert: Option<PathBuf>,
+    pub tls_key: Option<PathBuf>,
+    pub unix_socket: Option<PathBuf>,
+    pub unix_socket_mode: Option<String>,
+}
+
+/// A size given either as a number of bytes or as a string like `"8M"`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(untagged)]
+pub enum Size {
+    Bytes(usize),
+    Text(String),
+}
+
+impl FileConfig {
+    pub fn parse(content: &str) -> Result<Self, String> {
+        toml::from_str(content).map_err(|e| e.to_string())
+    }
+
+    pub fn load(path: &Path) -> Result<Self, ConfigError> {
+        let content = std::fs::read_to_string(path).map_err(|source| ConfigError::Read {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        Self::parse(&content).map_err(|message| ConfigError::Parse {
+            path: path.to_path_buf(),
+            message,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LogLevel {
+    Error,
+    Warn,
+    #[default]
+    Info,
+    Debug,
+    Trace,
+}
+
+impl FromStr for LogLevel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "error" => Ok(LogLevel::Error),
+            "warn" => Ok(LogLevel::Warn),
+            "info" => Ok(LogLevel::Info),
+            "debug" => Ok(LogLevel::Debug),
+            "trace" => Ok(LogLevel::Trace),
+            _ => Err(format!(
+                "unknown log level: {s} (expected error, warn, info, debug or trace)"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for LogLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            LogLevel::Error => "error",
+            LogLevel::Warn => "warn",
+            LogLevel::Info => "info",
+            LogLevel::Debug => "debug",
+            LogLevel::Trace => "trace",
+        })
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("cannot read config file {}: {source}", path.display())]
+    Read {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("invalid config file {}: {message}", path.display())]
+    Parse { path: PathBuf, message: String },
+    #[error("invalid {setting}: {message}")]
+    Invalid {
+        setting: &'static str,
+        message: String,
+    },
+}
+
+fn invalid(setting: &'static str) -> impl FnOnce(String) -> ConfigError {
+    move |message| ConfigError::Invalid { setting, message }
+}
+
+/// The validated server settings.
+#[derive(Debug, Clone)]
+pub struct ServerConfig {
+    pub listen: SocketAddr,
+    pub data_file: PathBuf,
+    pub log_level: LogLevel,
+    pub log_format: LogFormat,
+    pub shutdown_timeout: Duration,
+    pub on_load_error: LoadErrorPolicy,
+    pub tls: Option<TlsFiles>,
+    pub unix_socket: Option<UnixSocket>,
+    pub app: AppConfig,
+}
+
+impl ServerConfig {
+    /// Loads the `--config` file, if any, and resolves it with `cli`.
+    pub fn load(cli: Cli) -> Result<Self, ConfigError> {
+        let file = match &cli.config {
+            Some(path) => FileConfig::load(path)?,
+            None => FileConfig::default(),
+        };
+        Self::resolve(cli, file)
+    }
+
+    /// Merges flags (and their environment variables) over `file` over the
+    /// defaults and validates the result.
+    pub fn resolve(cli: Cli, file: FileConfig) -> Result<Self, ConfigError> {
+        let listen = cli
+            .listen
+            .or(file.listen)
+            .unwrap_or_else(|| DEFAULT_LISTEN.to_string());
+        let listen = listen
+            .parse::<SocketAddr>()
+            .map_err(|e| format!("{listen}: {e} (expected e.g. {DEFAULT_LISTEN})"))
+            .map_err(invalid("listen address"))?;
+
+        let data_file = cli
+            .data_file
+            .or(file.data_file)
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_DATA_FILE));
+        check_file_path(&data_file).map_err(invalid("data file"))?;
+
+        let max_body = match cli.max_body.map(Size::Text).or(file.max_body) {
+            Some(Size::Bytes(n)) => Some(n),
+            Some(Size::Text(s)) => Some(parse_size(&s).map_err(invalid("max body"))?),
+            None => None,
+        };
+        if max_body == Some(0) {
+            return Err(invalid("max body")("must be greater than 0".to_string()));
+        }
+
+        let min_size = match cli
+            .compress_min_size
+            .map(Size::Text)
+            .or(file.compress_min_size)
+        {
+            Some(Size::Bytes(n)) => n,
+            Some(Size::Text(s)) => parse_size(&s).map_err(invalid("compress min size"))?,
+            None => DEFAULT_COMPRESS_MIN_SIZE.into(),
+        };
+        let compression = Compression {
+            enabled: !cli.no_compression && file.compression.unwrap_or(true),
+            min_size: u16::try_from(min_size)
+                .map_err(|_| format!("{min_size} (at most 65535 bytes)"))
+                .map_err(invalid("compress min size"))?,
+        };
+
+        let log_level = match cli.log_level.or(file.log_level) {
+            Some(s) => s.parse().map_err(invalid("log level"))?,
+            None => LogLevel::default(),
+        };
+        let log_format = match cli.log_format.or(file.log_format) {
+            Some(s) => s.parse().map_err(invalid("log format"))?,
+            None => LogFormat::default(),
+        };
+        let on_load_error = match cli.on_load_error.or(file.on_load_error) {
+            Some(s) => s.parse().map_err(invalid("load error policy"))?,
+            None => LoadErrorPolicy::default(),
+        };
+        let error_format = match cli.error_format.or(file.error_format) {
+            Some(s) => s.parse().map_err(invalid("error format"))?,
+            None => ErrorFormat::default(),
+        };
+
+        let shutdown_timeout = match cli.shutdown_timeout {
+            Some(s) => s
+                .parse::<u64>()
+                .map(Duration::from_secs)
+                .map_err(|_| format!("{s} (expected whole seconds)"))
+                .map_err(invalid("shutdown timeout"))?,
+            None => file
+                .shutdown_timeout
+                .map_or(DEFAULT_DRAIN_TIMEOUT, Duration::from_secs),
+        };
+
+        let tls = match (cli.tls_cert.or(file.tls_cert), cli.tls_key.or(file.tls_key)) {
+            (Some(cert), Some(key)) => Some(TlsFiles { cert, key }),
+            (None, None) => None,
+            _ => {
+                return Err(invalid("TLS settings")(
+                    "a certificate and a key must be given together".to_string(),
+                ));
+            }
+        };
+
+        let mode = match cli.unix_socket_mode.or(file.unix_socket_mode) {
+            Some(s) => parse_mode(&s).map_err(invalid("unix socket mode"))?,
+            None => DEFAULT_UNIX_SOCKET_MODE,
+        };
+        let unix_socket = cli.unix_socket.or(file.unix_socket);
+        if let Some(path) = &unix_socket {
+            check_file_path(path).map_err(invalid("unix socket"))?;
+        }
+        let unix_socket = unix_socket.map(|path| UnixSocket { path, mode });
+
+        let text_prefix = match cli.text_prefix.or(file.text_prefix) {
+            Some(s) => Some(s.parse::<PathPrefix>().map_err(invalid("text prefix"))?),
+            None => None,
+        };
+        let kv_prefix = match cli.kv_prefix.or(file.kv_prefix) {
+            Some(s) => Some(s.parse::<PathPrefix>().map_err(invalid("kv prefix"))?),
+            None => None,
+        };
+
+        Ok(ServerConfig {
+            listen,
+            data_file,
+            log_level,
+            log_format,
+            shutdown_timeout,
+            on_load_error,
+            tls,
+            unix_socket,
+            app: AppConfig {
+                text_prefix,
+                kv_prefix,
+                max_body,
+                read_only: cli.read_only.or(file.read_only).unwrap_or(false),
+                error_format,
+                compression,
+                ..AppConfig::default()
+            },
+        })
+    }
+}
+
+/// Bytes, optionally with a binary `K`, `M` or `G` suffix, like `512` or `8M`.
+fn parse_size(s: &str) -> Result<usize, String> {
+    let trimmed = s.trim();
+    let (digits, multiplier) = match trimmed.char_indices().last() {
+        Some((i, 'k' | 'K')) => (&trimmed[..i], 1 << 10),
+        Some((i, 'm' | 'M')) => (&trimmed[..i], 1 << 20),
+        Some((i, 'g' | 'G')) => (&trimmed[..i], 1 << 30),
+        _ => (trimmed, 1),
+    };
+    digits
+        .parse::<usize>()
+        .ok()
+        .filter(|_| !digits.starts_with('+'))
+        .and_then(|n| n.checked_mul(multiplier))
+        .ok_or_else(|| format!("{s} (expected bytes or a number with a K, M or G suffix)"))
+}
+
+/// Permission bits in octal, like `660` or `0o600`.
+fn parse_mode(s: &str) -> Result<u32, String> {
+    let digits = s.strip_prefix("0o").unwrap_or(s);
+    match u32::from_str_radix(digits, 8) {
+        Ok(mode) if !digits.starts_with('+') && mode <= 0o777 => Ok(mode),
+        _ => Err(format!("{s} (expected octal permissions like 660)")),
+    }
+}
+
+/// The file may be missing, but its directory must exist and the path must
+/// not be a directory.
+fn check_file_path(path: &Path) -> Result<(), String> {
+    if path.as_os_str().is_empty() {
+        return Err("path is empty".to_string());
+    }
+    if path.is_dir() {
+        return Err(format!("{} is a directory", path.display()));
+    }
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    if !dir.is_dir() {
+        return Err(format!(
+            "{}: directory {} does not exist",
+            path.display(),
+            dir.display()
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        Cli, ConfigError, FileConfig, LogLevel, ServerConfig, Size, parse_mode, parse_size,
+    };
+    use crate::error::ErrorFormat;
+    use crate::storage::recovery::LoadErrorPolicy;
+    use crate::telemetry::LogFormat;
+    use clap::Parser;
+
+    fn cli(args: &[&str]) -> Cli {
+        Cli::try_parse_from(std::iter::once("store").chain(args.iter().copied())).unwrap()
+    }
+
+    #[test]
+    fn defaults_without_flags_or_file() {
+        let config = ServerConfig::resolve(Cli::default(), FileConfig::default()).unwrap();
+
+        assert_eq!("127.0.0.1:3000", config.listen.to_string());
+        assert_eq!("data.json", config.data_file.to_str().unwrap());
+        assert_eq!(LogLevel::Info, config.log_level);
+        assert_eq!(None, config.app.max_body);
+        assert!(!config.app.read_only);
+        assert_eq!(30, config.shutdown_timeout.as_secs());
+    }
+
+    #[test]
+    fn flags_override_file() {
+        let file = FileConfig::parse(
+            r#"
+            listen = "0.0.0.0:8080"
+            max_body = 4096
+            log_level = "debug"
+            log_format = "json"
+            read_only = true
+            on_load_error = "read-only"
+            error_format = "problem"
+            shutdown_timeout = 5
+            "#,
+        )
+        .unwrap();
+
+        let config = ServerConfig::resolve(
+            cli(&["--listen", "127.0.0.1:9000", "--max-body", "2K"]),
+            file,
+        )
+        .unwrap();
+
+        assert_eq!(9000, config.listen.port());
+        assert_eq!(Some(2048), config.app.max_body);
+        assert_eq!(LogLevel::Debug, config.log_level);
+        assert_eq!(LogFormat::Json, config.log_format);
+        assert!(config.app.read_only);
+        assert_eq!(LoadErrorPolicy::ReadOnly, config.on_load_error);
+        assert_eq!(ErrorFormat::Problem, config.app.error_format);
+        assert_eq!(5, config.shutdown_timeout.as_secs());
+    }
+
+    #[test]
+    fn read_only_flag_can_turn_off_the_file_setting() {
+        let file = FileConfig::parse("read_only = true").unwrap();
+        let resolve = |args: &[&str]| {
+            ServerConfig::resolve(cli(args), file.clone())
+                .unwrap()
+                .app
+                .read_only
+        };
+
+        assert!(resolve(&[]));
+        assert!(resolve(&["--read-only"]));
+        assert!(!resolve(&["--read-only=false"]));
+        assert!(!resolve(&["--read-only=0"]));
+
+        let config = ServerConfig::resolve(cli(&["--read-only"]), FileConfig::default()).unwrap();
+        assert!(config.app.read_only);
+        assert!(Cli::try_parse_from(["store", "--read-only=maybe"]).is_err());
+    }
+
+    #[test]
+    fn max_body_accepts_number_or_size_in_file() {
+        let file = FileConfig::parse("max_body = \"1M\"").unwrap();
+        assert_eq!(Some(Size::Text("1M".to_string())), file.max_body);
+
+        let config = ServerConfig::resolve(Cli::default(), file).unwrap();
+        assert_eq!(Some(1 << 20), config.app.max_body);
+    }
+
+    #[test]
+    fn invalid_values_name_the_setting() {
+        let err = ServerConfig::resolve(cli(&["--listen", "localhost"]), FileConfig::default())
+            .unwrap_err();
+        assert!(
+            err.to_string()
+                .starts_with("invalid listen address: localhost")
+        );
+
+        let err =
+            ServerConfig::resolve(cli(&["--max-body", "lots"]), FileConfig::default()).unwrap_err();
+        assert!(err.to_string().starts_with("invalid max body: lots"));
+
+        let err = ServerConfig::resolve(cli(&["--log-level", "loud"]), FileConfig::default())
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            ConfigError::Invalid {
+                setting: "log level",
+                ..
+            }
+        ));
+
+        let err =
+            ServerConfig::resolve(cli(&["--kv-prefix", "kv/"]), FileConfig::default()).unwrap_err();
+        assert!(err.to_string().starts_with("invalid kv prefix"));
+
+        let err = ServerConfig::resolve(
+            cli(&["--data-file", "/no/such/dir/data.json"]),
+            FileConfig::default(),
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("does not exist"));
+    }
+
+    #[test]
+    fn tls_and_unix_socket_settings() {
+        let file = FileConfig::parse(
+            r#"
+            tls_cert = "cert.pem"
+            unix_socket = "store.sock"
+            "#,
+        )
+        .unwrap();
+        let err = ServerConfig::resolve(Cli::default(), file.clone()).unwrap_err();
+        assert!(err.to_string().starts_with("invalid TLS settings"));
+
+        let config = ServerConfig::resolve(cli(&["--tls-key", "key.pem"]), file.clone()).unwrap();
+        let tls = config.tls.unwrap();
+        assert_eq!("cert.pem", tls.cert.to_str().unwrap());
+        assert_eq!("key.pem", tls.key.to_str().unwrap());
+        let socket = config.unix_socket.unwrap();
+        assert_eq!("store.sock", socket.path.to_str().unwrap());
+        assert_eq!(0o660, socket.mode);
+
+        let config = ServerConfig::resolve(
+            cli(&["--tls-key", "key.pem", "--unix-socket-mode", "600"]),
+            file,
+        )
+        .unwrap();
+        assert_eq!(0o600, config.unix_socket.unwrap().mode);
+
+        let config = ServerConfig::resolve(Cli::default(), FileConfig::default()).unwrap();
+        assert!(config.tls.is_none());
+        assert!(config.unix_socket.is_none());
+    }
+
+    #[test]
+    fn sizes_take_binary_suffixes() {
+        assert_eq!(Ok(512), parse_size("512"));
+        assert_eq!(Ok(2048), parse_size("2K"));
+        assert_eq!(Ok(8 << 20), parse_size("8m"));
+        assert_eq!(Ok(1 << 30), parse_size(" 1G "));
+        assert!(parse_size("+1K").is_err());
+        assert!(parse_size("1T").is_err());
+        assert!(parse_size("K").is_err());
+
+        let err =
+            ServerConfig::resolve(cli(&["--max-body", "8MB"]), FileConfig::default()).unwrap_err();
+        assert_eq!(
+            "invalid max body: 8MB (expected bytes or a number with a K, M or G suffix)",
+            err.to_string()
+        );
+        let file = FileConfig::parse("compress_min_size = \"half\"").unwrap();
+        let err = ServerConfig::resolve(Cli::default(), file).unwrap_err();
+        assert!(matches!(
+            err,
+            ConfigError::Invalid {
+                setting: "compress min size",
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn unix_socket_mode_is_octal() {
+        assert_eq!(Ok(0o660), parse_mode("660"));
+        assert_eq!(Ok(0o600), parse_mode("0600"));
+        assert_eq!(Ok(0o777), parse_mode("0o777"));
+        assert!(parse_mode("800").is_err());
+        assert!(parse_mode("1777").is_err());
+        assert!(parse_mode("+660").is_err());
+        assert!(parse_mode("rw").is_err());
+    }
+
+    #[test]
+    fn compression_settings() {
+        let config = ServerConfig::resolve(Cli::default(), FileConfig::default()).unwrap();
+        assert!(config.app.compression.enabled);
+        assert_eq!(1024, config.app.compression.min_size);
+
+        let file = FileConfig::parse("compression = false\ncompress_min_size = 256").unwrap();
+        let config = ServerConfig::resolve(Cli::default(), file).unwrap();
+        assert!(!config.app.compression.enabled);
+        assert_eq!(256, config.app.compression.min_size);
+
+        let config = ServerConfig::resolve(
+            cli(&["--no-compression", "--compress-min-size", "4K"]),
+            FileConfig::default(),
+        )
+        .unwrap();
+        assert!(!config.app.compression.enabled);
+        assert_eq!(4096, config.app.compression.min_size);
+
+        let err = ServerConfig::resolve(cli(&["--compress-min-size", "1M"]), FileConfig::default())
+            .unwrap_err();
+        assert!(
+            err.to_string()
+                .starts_with("invalid compress min size: 1048576")
+        );
+    }
+
+    #[test]
+    fn file_rejects_unknown_keys() {
+        let err = FileConfig::parse("listen_addr = \"127.0.0.1:1\"").unwrap_err();
+        assert!(err.contains("listen_addr"));
+    }
+}
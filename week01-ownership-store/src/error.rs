@@ -1,8 +1,18 @@
 use axum::Json;
-use axum::http::StatusCode;
+use axum::Router;
+use axum::extract::{Request, State};
+use axum::http::header::{ACCEPT, CONTENT_TYPE, HeaderValue};
+use axum::http::{HeaderMap, StatusCode};
+use axum::middleware::{self, Next};
 use axum::response::{IntoResponse, Response};
-use serde_json::json;
+use serde::Serialize;
+use serde_json::{Value, json};
+use std::str::FromStr;
 use thiserror::Error;
+use utoipa::ToSchema;
+
+pub const PROBLEM_JSON: &str = "application/problem+json";
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
 
 #[allow(dead_code)]
 #[derive(Debug, Error)]
@@ -22,36 +32,249 @@ pub enum StoreError {
     UnsupportedVersion(u32),
 }
 
+/// The error type of every route, `/v1/*` and `/kv/*` alike.
 #[derive(Debug)]
 pub enum ApiError {
+    Validation(String),
     NotFound,
+    /// A body the extractor rejected, with the status it chose.
+    InvalidBody(StatusCode, String),
     InvalidJson(serde_json::Error),
+    TooLarge(String),
+    /// A configured limit was exceeded; the limit is reported in `details`.
+    LimitExceeded {
+        /// A singular noun phrase, e.g. `"line count"`, for "{what} exceeds {limit}".
+        what: &'static str,
+        limit: usize,
+    },
+    /// A write to the store while the server runs read-only.
+    ReadOnly,
     Io(std::io::Error),
-    #[allow(dead_code)]
     Internal(String),
 }
 
+/// The `error` member of the JSON envelope.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ErrorInfo {
+    /// Stable machine-readable code, e.g. `NOT_FOUND`.
+    pub code: &'static str,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+}
+
+/// `{"error": {"code", "message", "details"?, "request_id"?}}`
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ErrorBody {
+    pub error: ErrorInfo,
+}
+
+impl ApiError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            ApiError::Validation(_) | ApiError::InvalidJson(_) => StatusCode::BAD_REQUEST,
+            ApiError::NotFound => StatusCode::NOT_FOUND,
+            ApiError::ReadOnly => StatusCode::FORBIDDEN,
+            ApiError::InvalidBody(status, _) => *status,
+            ApiError::TooLarge(_) | ApiError::LimitExceeded { .. } => StatusCode::PAYLOAD_TOO_LARGE,
+            ApiError::Io(_) | ApiError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    pub fn into_info(self) -> ErrorInfo {
+        let (code, message, details) = match self {
+            ApiError::Validation(msg) => ("VALIDATION_ERROR", msg, None),
+            ApiError::NotFound => ("NOT_FOUND", "key does not exist".to_string(), None),
+            ApiError::InvalidBody(_, msg) => ("INVALID_BODY", msg, None),
+            ApiError::InvalidJson(err) => ("INVALID_BODY", err.to_string(), None),
+            ApiError::TooLarge(msg) => ("PAYLOAD_TOO_LARGE", msg, None),
+            ApiError::LimitExceeded { what, limit } => (
+                "PAYLOAD_TOO_LARGE",
+                format!("{what} exceeds {limit}"),
+                Some(json!({ "limit": limit })),
+            ),
+            ApiError::ReadOnly => ("READ_ONLY", "the store is read-only".to_string(), None),
+            ApiError::Io(err) => ("STORAGE_ERROR", err.to_string(), None),
+            ApiError::Internal(msg) => ("INTERNAL_ERROR", msg, None),
+        };
+
+        ErrorInfo {
+            code,
+            message,
+            details,
+            request_id: None,
+        }
+    }
+
+    /// The flat `{"error": code, "message"}` shape and lowercase code the
+    /// `/kv` routes used before the envelope was unified; `None` for errors
+    /// that always used the envelope.
+    fn legacy_flat_code(&self) -> Option<&'static str> {
+        match self {
+            ApiError::NotFound => Some("not_found"),
+            ApiError::InvalidJson(_) => Some("invalid_json"),
+            ApiError::Io(_) => Some("io_error"),
+            _ => None,
+        }
+    }
+}
+
+/// How error responses are rendered.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ErrorFormat {
+    /// The JSON envelope, or problem+json when the client asks for it.
+    #[default]
+    Envelope,
+    /// RFC 7807 `application/problem+json` for every error.
+    Problem,
+    /// Each route's pre-unification shape, for clients that still expect it.
+    Legacy,
+}
+
+impl FromStr for ErrorFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "envelope" => Ok(ErrorFormat::Envelope),
+            "problem" => Ok(ErrorFormat::Problem),
+            "legacy" => Ok(ErrorFormat::Legacy),
+            _ => Err(format!(
+                "unknown error format: {s} (expected envelope, problem or legacy)"
+            )),
+        }
+    }
+}
+
+/// Attached to every error response so [`render_errors`] can re-render it.
+#[derive(Debug, Clone)]
+struct ErrorReport {
+    status: StatusCode,
+    info: ErrorInfo,
+    legacy_flat_code: Option<&'static str>,
+}
+
 impl IntoResponse for ApiError {
     fn into_response(self) -> Response {
-        let (status, code, message): (StatusCode, &str, String) = match self {
-            ApiError::NotFound => (
-                StatusCode::NOT_FOUND,
-                "not_found",
-                "key does not exist".to_string(),
-            ),
-            ApiError::InvalidJson(err) => {
-                (StatusCode::BAD_REQUEST, "invalid_json", err.to_string())
-            }
-            ApiError::Io(err) => (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                "io_error",
-                err.to_string(),
-            ),
-            ApiError::Internal(msg) => (StatusCode::INTERNAL_SERVER_ERROR, "internal", msg),
+        let status = self.status();
+        let legacy_flat_code = self.legacy_flat_code();
+        let report = ErrorReport {
+            status,
+            info: self.into_info(),
+            legacy_flat_code,
         };
 
-        let body = Json(json!({"error": code, "message": message}));
+        let mut resp = envelope(&report);
+        resp.extensions_mut().insert(report);
+        resp
+    }
+}
+
+fn envelope(report: &ErrorReport) -> Response {
+    let body = ErrorBody {
+        error: report.info.clone(),
+    };
+    (report.status, Json(body)).into_response()
+}
+
+fn problem(report: &ErrorReport) -> Response {
+    let mut body = json!({
+        "type": "about:blank",
+        "title": report.status.canonical_reason().unwrap_or("Error"),
+        "status": report.status.as_u16(),
+        "detail": report.info.message,
+        "code": report.info.code,
+    });
+    if let Some(details) = &report.info.details {
+        body["details"] = details.clone();
+    }
+    if let Some(id) = &report.info.request_id {
+        body["request_id"] = json!(id);
+    }
+
+    let mut resp = (report.status, Json(body)).into_response();
+    resp.headers_mut()
+        .insert(CONTENT_TYPE, HeaderValue::from_static(PROBLEM_JSON));
+    resp
+}
 
-        (status, body).into_response()
+fn legacy(report: &ErrorReport) -> Response {
+    match report.legacy_flat_code {
+        Some(code) => (
+            report.status,
+            Json(json!({ "error": code, "message": report.info.message })),
+        )
+            .into_response(),
+        None => {
+            let body = ErrorBody {
+                error: ErrorInfo {
+                    details: None,
+                    request_id: None,
+                    ..report.info.clone()
+                },
+            };
+            (report.status, Json(body)).into_response()
+        }
     }
 }
+
+fn accepts_problem(headers: &HeaderMap) -> bool {
+    headers
+        .get_all(ACCEPT)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .filter_map(|v| v.split(';').next())
+        .any(|media| media.trim().eq_ignore_ascii_case(PROBLEM_JSON))
+}
+
+/// Middleware that renders [`ApiError`] responses in the configured format
+/// and adds the request id from the `x-request-id` header.
+pub async fn render_errors(
+    State(format): State<ErrorFormat>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let request_id = req
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+    let wants_problem = accepts_problem(req.headers());
+
+    let resp = next.run(req).await;
+    let Some(mut report) = resp.extensions().get::<ErrorReport>().cloned() else {
+        return resp;
+    };
+    report.info.request_id = request_id;
+    if report.status.is_server_error() {
+        // `trace_requests` logs the failure itself; this adds the cause
+        tracing::debug!(
+            code = report.info.code,
+            message = %report.info.message,
+            "request error"
+        );
+    }
+
+    let mut rendered = match format {
+        ErrorFormat::Legacy => legacy(&report),
+        ErrorFormat::Problem => problem(&report),
+        ErrorFormat::Envelope if wants_problem => problem(&report),
+        ErrorFormat::Envelope => envelope(&report),
+    };
+
+    // keep headers set by the handler or other layers, but not the old body's
+    let (mut parts, _) = resp.into_parts();
+    parts.headers.remove(CONTENT_TYPE);
+    parts.headers.remove(axum::http::header::CONTENT_LENGTH);
+    rendered.headers_mut().extend(parts.headers);
+    *rendered.extensions_mut() = std::mem::take(&mut parts.extensions);
+    rendered
+}
+
+/// Applies [`render_errors`] with `format` to every route of `router`.
+pub fn with_format(router: Router, format: ErrorFormat) -> Router {
+    router.layer(middleware::from_fn_with_state(format, render_errors))
+}
@@ -0,0 +1,165 @@
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use serde::Serialize;
+use serde_json::json;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::api::state::AppState;
+
+/// What `/readyz` needs to know beyond the store itself.
+#[derive(Debug, Clone, Default)]
+pub struct Readiness {
+    /// Why the data file could not be loaded at startup, if it could not.
+    pub load_error: Option<String>,
+    /// Writes are rejected, so the data directory need not be writable.
+    pub read_only: bool,
+    /// The last probe of the data directory, reused until a save happens or
+    /// [`PROBE_INTERVAL`] passes.
+    pub probe: ProbeCache,
+}
+
+/// How long the result of a data directory probe is reused.
+pub const PROBE_INTERVAL: Duration = Duration::from_secs(30);
+
+type CheckResult = Result<Option<String>, String>;
+
+/// The result of the latest writability probe and when it ran.
+#[derive(Debug, Clone, Default)]
+pub struct ProbeCache(Arc<Mutex<Option<(Instant, CheckResult)>>>);
+
+impl ProbeCache {
+    fn fresh(&self) -> Option<CheckResult> {
+        match &*self.0.lock().unwrap() {
+            Some((at, result)) if at.elapsed() < PROBE_INTERVAL => Some(result.clone()),
+            _ => None,
+        }
+    }
+
+    fn store(&self, result: CheckResult) {
+        *self.0.lock().unwrap() = Some((Instant::now(), result));
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Check {
+    pub name: &'static str,
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+impl Check {
+    fn new(name: &'static str, result: CheckResult) -> Self {
+        match result {
+            Ok(detail) => Check {
+                name,
+                ok: true,
+                detail,
+            },
+            Err(detail) => Check {
+                name,
+                ok: false,
+                detail: Some(detail),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReadyReport {
+    /// `ready` or `not_ready`.
+    pub status: &'static str,
+    pub checks: Vec<Check>,
+}
+
+/// Creates and removes a probe file next to `data_file`, the same way a save
+/// writes its temporary file.
+async fn probe_writable(data_file: &Path) -> CheckResult {
+    let probe = data_file.with_extension("readyz");
+    tokio::fs::write(&probe, b"")
+        .await
+        .map_err(|e| format!("cannot write {}: {e}", probe.display()))?;
+    let _ = tokio::fs::remove_file(&probe).await;
+    Ok(None)
+}
+
+/// Once the data file has been saved, the outcome of the latest save answers
+/// whether its directory is writable. Before that a probe file is written,
+/// at most once per [`PROBE_INTERVAL`].
+async fn check_writable(state: &AppState, probe: &ProbeCache) -> CheckResult {
+    match state.metrics.last_save_succeeded() {
+        Some(true) => return Ok(None),
+        Some(false) => return Err("the latest save of the data file failed".to_string()),
+        None => {}
+    }
+    if let Some(result) = probe.fresh() {
+        return result;
+    }
+    let result = probe_writable(Path::new(&state.data_file)).await;
+    probe.store(result.clone());
+    result
+}
+
+/// Runs every readiness check.
+pub async fn ready_report(state: &AppState, readiness: &Readiness) -> ReadyReport {
+    let data_file = Check::new(
+        "data_file",
+        match &readiness.load_error {
+            Some(err) => Err(format!("not loaded: {err}")),
+            None => Ok(None),
+        },
+    );
+    let writable = Check::new(
+        "data_dir_writable",
+        if readiness.read_only {
+            Ok(Some("skipped, read-only".to_string()))
+        } else {
+            check_writable(state, &readiness.probe).await
+        },
+    );
+    let last_save = Check::new(
+        "last_save",
+        match state.metrics.last_save_succeeded() {
+            Some(true) => Ok(None),
+            Some(false) => Err("the latest save of the data file failed".to_string()),
+            None => Ok(Some("no save yet".to_string())),
+        },
+    );
+
+    let checks = vec![data_file, writable, last_save];
+    let status = if checks.iter().all(|c| c.ok) {
+        "ready"
+    } else {
+        "not_ready"
+    };
+    ReadyReport { status, checks }
+}
+
+async fn healthz() -> Json<serde_json::Value> {
+    Json(json!({ "status": "ok" }))
+}
+
+async fn readyz(State((state, readiness)): State<(AppState, Readiness)>) -> Response {
+    let report = ready_report(&state, &readiness).await;
+    let status = if report.status == "ready" {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (status, Json(report)).into_response()
+}
+
+/// `/healthz` answers as long as the process serves requests; `/readyz`
+/// answers `503` while the data file is not loaded, its directory is not
+/// writable or the latest save failed.
+pub fn router(state: AppState, readiness: Readiness) -> Router {
+    Router::new()
+        .route("/healthz", get(healthz))
+        .route("/readyz", get(readyz))
+        .with_state((state, readiness))
+}
@@ -0,0 +1,141 @@
+use axum::http::HeaderMap;
+use axum::http::header::ACCEPT;
+
+/// One media range of an `Accept` header, e.g. `application/*;q=0.5`.
+struct MediaRange<'a> {
+    kind: &'a str,
+    subtype: &'a str,
+    q: f32,
+}
+
+impl<'a> MediaRange<'a> {
+    fn parse(range: &'a str) -> Option<Self> {
+        let mut parts = range.split(';');
+        let (kind, subtype) = parts.next()?.trim().split_once('/')?;
+        let mut q = 1.0;
+        for param in parts {
+            if let Some((name, value)) = param.split_once('=')
+                && name.trim().eq_ignore_ascii_case("q")
+            {
+                q = value
+                    .trim()
+                    .parse::<f32>()
+                    .ok()
+                    .filter(|q| (0.0..=1.0).contains(q))?;
+            }
+        }
+        Some(MediaRange {
+            kind: kind.trim(),
+            subtype: subtype.trim(),
+            q,
+        })
+    }
+
+    /// How closely the range matches `media`: 3 for an exact match, 2 for
+    /// `type/*`, 1 for `*/*`.
+    fn specificity(&self, media: &str) -> Option<u8> {
+        let (kind, subtype) = media.split_once('/')?;
+        match (self.kind, self.subtype) {
+            ("*", "*") => Some(1),
+            (k, "*") if k.eq_ignore_ascii_case(kind) => Some(2),
+            (k, s) if k.eq_ignore_ascii_case(kind) && s.eq_ignore_ascii_case(subtype) => Some(3),
+            _ => None,
+        }
+    }
+}
+
+/// The quality the client gives `media`: that of the most specific range
+/// matching it, or 0 if none does.
+fn quality(ranges: &[MediaRange<'_>], media: &str) -> f32 {
+    ranges
+        .iter()
+        .filter_map(|range| range.specificity(media).map(|s| (s, range.q)))
+        .max_by_key(|(specificity, _)| *specificity)
+        .map_or(0.0, |(_, q)| q)
+}
+
+/// Picks the type in `offered` the client prefers, going by the `q` values of
+/// its `Accept` header. Ties go to the earlier offer, and so does a request
+/// without `Accept`. `None` if the client accepts none of them.
+pub fn preferred<'a>(headers: &HeaderMap, offered: &[&'a str]) -> Option<&'a str> {
+    let values: Vec<&str> = headers
+        .get_all(ACCEPT)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .collect();
+    if values.is_empty() {
+        return offered.first().copied();
+    }
+    let ranges: Vec<MediaRange<'_>> = values
+        .iter()
+        .flat_map(|v| v.split(','))
+        .filter_map(MediaRange::parse)
+        .collect();
+
+    let mut best: Option<(&str, f32)> = None;
+    for &media in offered {
+        let q = quality(&ranges, media);
+        if q > 0.0 && best.is_none_or(|(_, best_q)| q > best_q) {
+            best = Some((media, q));
+        }
+    }
+    best.map(|(media, _)| media)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::preferred;
+    use axum::http::{HeaderMap, HeaderValue, header::ACCEPT};
+
+    const OFFERED: &[&str] = &["application/json", "application/x-ndjson"];
+
+    fn pick(accept: &str) -> Option<&'static str> {
+        let mut headers = HeaderMap::new();
+        headers.insert(ACCEPT, HeaderValue::from_str(accept).unwrap());
+        preferred(&headers, OFFERED)
+    }
+
+    #[test]
+    fn no_accept_header_gets_the_first_offer() {
+        assert_eq!(
+            Some("application/json"),
+            preferred(&HeaderMap::new(), OFFERED)
+        );
+    }
+
+    #[test]
+    fn q_values_decide() {
+        assert_eq!(Some("application/x-ndjson"), pick("application/x-ndjson"));
+        assert_eq!(
+            Some("application/json"),
+            pick("application/json, application/x-ndjson;q=0.9")
+        );
+        assert_eq!(
+            Some("application/x-ndjson"),
+            pick("application/json;q=0.5, application/x-ndjson")
+        );
+        assert_eq!(None, pick("application/x-ndjson;q=0"));
+    }
+
+    #[test]
+    fn wildcards_are_less_specific() {
+        assert_eq!(Some("application/json"), pick("*/*"));
+        assert_eq!(Some("application/json"), pick("application/*"));
+        assert_eq!(
+            Some("application/x-ndjson"),
+            pick("application/x-ndjson, */*;q=0.1")
+        );
+        assert_eq!(
+            Some("application/x-ndjson"),
+            pick("*/*, application/json;q=0")
+        );
+    }
+
+    #[test]
+    fn malformed_ranges_are_ignored() {
+        assert_eq!(
+            Some("application/x-ndjson"),
+            pick("nonsense, application/json;q=2, application/x-ndjson")
+        );
+    }
+}
@@ -0,0 +1,157 @@
+use axum::{
+    extract::{
+        FromRequest, Multipart, Request,
+        multipart::{MultipartError, MultipartRejection},
+        rejection::{JsonRejection, StringRejection},
+    },
+    http::{StatusCode, header::CONTENT_TYPE},
+};
+use serde::de::DeserializeOwned;
+
+use super::errors::ApiError;
+
+/// `Json` that reports rejections in the API's error format.
+pub struct ApiJson<T>(pub T);
+
+impl<T, S> FromRequest<S> for ApiJson<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        match axum::Json::<T>::from_request(req, state).await {
+            Ok(axum::Json(value)) => Ok(ApiJson(value)),
+            Err(rejection) => Err(rejection.into()),
+        }
+    }
+}
+
+/// A request type that can also be built from raw text plus its other
+/// fields, given as query parameters or multipart form fields.
+pub trait TextPayload: DeserializeOwned {
+    type Options: DeserializeOwned;
+
+    fn from_parts(text: String, options: Self::Options) -> Self;
+}
+
+/// A text request sent as JSON, as a `text/plain` body with options in the
+/// query string, or as a `multipart/form-data` upload whose `file` (or `text`)
+/// part holds the text and whose other fields hold the options. The query
+/// string is only read for `text/plain`; other requests may carry any query.
+pub struct TextRequest<T>(pub T);
+
+impl<T, S> FromRequest<S> for TextRequest<T>
+where
+    T: TextPayload,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let mime = req
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.split(';').next())
+            .map(|v| v.trim().to_ascii_lowercase())
+            .unwrap_or_default();
+        let mut fields: Vec<(String, String)> = Vec::new();
+
+        let text = match mime.as_str() {
+            "text/plain" => {
+                fields = serde_urlencoded::from_str(req.uri().query().unwrap_or(""))
+                    .map_err(|e| ApiError::Validation(format!("invalid query string: {e}")))?;
+                String::from_request(req, state).await?
+            }
+            "multipart/form-data" => {
+                let multipart = Multipart::from_request(req, state).await?;
+                read_multipart(multipart, &mut fields).await?
+            }
+            _ => {
+                let ApiJson(payload) = ApiJson::<T>::from_request(req, state).await?;
+                return Ok(TextRequest(payload));
+            }
+        };
+
+        let query =
+            serde_urlencoded::to_string(&fields).map_err(|e| ApiError::Internal(e.to_string()))?;
+        let options = serde_urlencoded::from_str::<T::Options>(&query).map_err(|e| {
+            ApiError::InvalidBody(
+                StatusCode::UNPROCESSABLE_ENTITY,
+                format!("invalid request options: {e}"),
+            )
+        })?;
+
+        Ok(TextRequest(T::from_parts(text, options)))
+    }
+}
+
+/// Returns the text part and appends every other field to `fields`.
+async fn read_multipart(
+    mut multipart: Multipart,
+    fields: &mut Vec<(String, String)>,
+) -> Result<String, ApiError> {
+    let mut text = None;
+
+    while let Some(field) = multipart.next_field().await? {
+        let name = field.name().unwrap_or_default().to_string();
+        match name.as_str() {
+            "file" | "text" => {
+                if text.is_some() {
+                    return Err(ApiError::Validation(
+                        "only one `file` or `text` part is allowed".to_string(),
+                    ));
+                }
+                let bytes = field.bytes().await?;
+                let value = String::from_utf8(bytes.to_vec()).map_err(|_| {
+                    ApiError::Validation(format!("`{name}` part is not valid UTF-8"))
+                })?;
+                text = Some(value);
+            }
+            _ => {
+                let value = field.text().await?;
+                fields.push((name, value));
+            }
+        }
+    }
+
+    text.ok_or_else(|| {
+        ApiError::Validation("multipart body needs a `file` or `text` part".to_string())
+    })
+}
+
+/// Maps an axum rejection to the API's error, keeping its status.
+fn rejected(status: StatusCode, message: String) -> ApiError {
+    match status {
+        StatusCode::PAYLOAD_TOO_LARGE => {
+            ApiError::TooLarge("request body exceeds the size limit".to_string())
+        }
+        status => ApiError::InvalidBody(status, message),
+    }
+}
+
+impl From<JsonRejection> for ApiError {
+    fn from(rejection: JsonRejection) -> Self {
+        rejected(rejection.status(), rejection.body_text())
+    }
+}
+
+impl From<StringRejection> for ApiError {
+    fn from(rejection: StringRejection) -> Self {
+        rejected(rejection.status(), rejection.body_text())
+    }
+}
+
+impl From<MultipartRejection> for ApiError {
+    fn from(rejection: MultipartRejection) -> Self {
+        rejected(rejection.status(), rejection.body_text())
+    }
+}
+
+impl From<MultipartError> for ApiError {
+    fn from(err: MultipartError) -> Self {
+        rejected(err.status(), err.body_text())
+    }
+}
@@ -1,37 +1,134 @@
+use crate::http::accept;
 use crate::http::errors::ApiError;
+use crate::http::extract::{ApiJson, TextRequest};
+use crate::http::limits::Limits;
 use crate::http::types::{
-    GrepMatch, GrepRequest, GrepResponse, StatsRequest, StatsResponse, UniqRequest, UniqResponse,
+    BatchItem, BatchOp, BatchOutput, BatchResponse, Distribution, ErrorBody, GrepMatch,
+    GrepOptions, GrepRequest, GrepResponse, GrepSummary, StatsOptions, StatsRequest, StatsResponse,
+    UniqOptions, UniqRequest, UniqResponse,
 };
-use axum::Json;
-use textkit::grep::grep_lines;
-use textkit::stats::analyze;
+use crate::metrics::Metrics;
+use axum::body::{Body, Bytes};
+use axum::extract::State;
+use axum::http::header::CONTENT_TYPE;
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::{Extension, Json};
+use futures_util::{StreamExt, stream};
+use serde::Serialize;
+use std::sync::Arc;
+use textkit::grep::{LineMatch, grep_iter};
+use textkit::stats::{analyze, distribution, line_lengths};
 use textkit::uniq::uniq_lines;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
 
-const MAX_TEXT_BYTES: usize = 1_048_576;
+const JSON: &str = "application/json";
+/// Media type of the streamed grep response.
+pub const NDJSON: &str = "application/x-ndjson";
+/// Matches buffered between the search and a slow client.
+const NDJSON_BUFFER: usize = 64;
 
-pub async fn stats(Json(payload): Json<StatsRequest>) -> Result<Json<StatsResponse>, ApiError> {
-    let bytes = payload.text.len();
-    if bytes > MAX_TEXT_BYTES {
-        return Err(ApiError::TooLarge("text exceeds 1MB limit".to_string()));
+/// Counts a text operation if the app keeps metrics; the bare text router
+/// does not.
+fn observe_text_op(metrics: Option<&Metrics>, op: &'static str, text: &str) {
+    if let Some(metrics) = metrics {
+        metrics.observe_text_op(op, text.len());
     }
+}
+
+fn check_lines(text: &str, limits: &Limits) -> Result<(), ApiError> {
+    // stop counting as soon as the limit is passed
+    if text.lines().nth(limits.max_lines).is_some() {
+        return Err(ApiError::LimitExceeded {
+            what: "line count",
+            limit: limits.max_lines,
+        });
+    }
+    Ok(())
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/stats",
+    tag = "text",
+    params(StatsOptions),
+    request_body(content(
+        (StatsRequest = "application/json"),
+        (String = "text/plain"),
+        (String = "multipart/form-data"),
+    )),
+    responses(
+        (status = 200, body = StatsResponse, description = "Counts, plus line lengths if requested"),
+        (status = 413, body = ErrorBody, description = "Body, text or batch over a configured limit"),
+    )
+)]
+pub async fn stats(
+    State(limits): State<Limits>,
+    metrics: Option<Extension<Arc<Metrics>>>,
+    TextRequest(payload): TextRequest<StatsRequest>,
+) -> Result<Json<StatsResponse>, ApiError> {
+    run_stats(payload, &limits, metrics.as_deref().map(Arc::as_ref)).map(Json)
+}
+
+pub fn run_stats(
+    payload: StatsRequest,
+    limits: &Limits,
+    metrics: Option<&Metrics>,
+) -> Result<StatsResponse, ApiError> {
+    check_lines(&payload.text, limits)?;
+    observe_text_op(metrics, "stats", &payload.text);
 
     let stats = match analyze(&payload.text) {
         Ok(v) => v,
         Err(e) => return Err(ApiError::Internal(e.to_string())),
     };
 
-    Ok(Json(StatsResponse {
+    let line_lengths = if payload.percentiles.unwrap_or(false) {
+        distribution(&line_lengths(&payload.text)).map(Distribution::from)
+    } else {
+        None
+    };
+
+    Ok(StatsResponse {
         lines: stats.lines,
         words: stats.words,
         chars: stats.chars,
         bytes: payload.text.len(),
-    }))
+        line_lengths,
+    })
 }
 
-pub async fn uniq(Json(payload): Json<UniqRequest>) -> Result<Json<UniqResponse>, ApiError> {
-    if payload.text.len() > MAX_TEXT_BYTES {
-        return Err(ApiError::TooLarge("text exceeds 1MB limit".to_string()));
-    }
+#[utoipa::path(
+    post,
+    path = "/v1/uniq",
+    tag = "text",
+    params(UniqOptions),
+    request_body(content(
+        (UniqRequest = "application/json"),
+        (String = "text/plain"),
+        (String = "multipart/form-data"),
+    )),
+    responses(
+        (status = 200, body = UniqResponse, description = "Text with duplicates removed"),
+        (status = 413, body = ErrorBody, description = "Body, text or batch over a configured limit"),
+    )
+)]
+pub async fn uniq(
+    State(limits): State<Limits>,
+    metrics: Option<Extension<Arc<Metrics>>>,
+    TextRequest(payload): TextRequest<UniqRequest>,
+) -> Result<Json<UniqResponse>, ApiError> {
+    run_uniq(payload, &limits, metrics.as_deref().map(Arc::as_ref)).map(Json)
+}
+
+pub fn run_uniq(
+    payload: UniqRequest,
+    limits: &Limits,
+    metrics: Option<&Metrics>,
+) -> Result<UniqResponse, ApiError> {
+    check_lines(&payload.text, limits)?;
+    observe_text_op(metrics, "uniq", &payload.text);
 
     let all = payload.all.unwrap_or(false);
 
@@ -43,49 +140,215 @@ pub async fn uniq(Json(payload): Json<UniqRequest>) -> Result<Json<UniqResponse>
     let out = lines.join("\n");
     let removed = payload.text.lines().count().saturating_sub(lines.len());
 
-    Ok(Json(UniqResponse {
+    Ok(UniqResponse {
         text: if out.is_empty() {
             String::new()
         } else {
             format!("{out}\n")
         },
         removed,
-    }))
+    })
 }
 
-pub async fn grep(Json(payload): Json<GrepRequest>) -> Result<Json<GrepResponse>, ApiError> {
-    if payload.text.len() > MAX_TEXT_BYTES {
-        return Err(ApiError::TooLarge("text exceeds 1MB limit".to_string()));
+#[utoipa::path(
+    post,
+    path = "/v1/grep",
+    tag = "text",
+    params(GrepOptions),
+    request_body(content(
+        (GrepRequest = "application/json"),
+        (String = "text/plain"),
+        (String = "multipart/form-data"),
+    )),
+    responses(
+        (
+            status = 200,
+            description = "All matches, or with `Accept: application/x-ndjson` one match per line and a trailing `GrepSummary`",
+            content(
+                (GrepResponse = "application/json"),
+                (GrepMatch = "application/x-ndjson"),
+            )
+        ),
+        (status = 400, body = ErrorBody, description = "Empty or too long pattern"),
+        (status = 413, body = ErrorBody, description = "Body, text or batch over a configured limit"),
+    )
+)]
+pub async fn grep(
+    State(limits): State<Limits>,
+    metrics: Option<Extension<Arc<Metrics>>>,
+    headers: HeaderMap,
+    TextRequest(payload): TextRequest<GrepRequest>,
+) -> Result<Response, ApiError> {
+    let metrics = metrics.as_deref().map(Arc::as_ref);
+    if wants_ndjson(&headers) {
+        check_grep(&payload, &limits, metrics)?;
+        return Ok(grep_ndjson(payload));
     }
 
+    run_grep(payload, &limits, metrics).map(|resp| Json(resp).into_response())
+}
+
+fn check_grep(
+    payload: &GrepRequest,
+    limits: &Limits,
+    metrics: Option<&Metrics>,
+) -> Result<(), ApiError> {
+    check_lines(&payload.text, limits)?;
+
     if payload.pattern.trim().is_empty() {
         return Err(ApiError::Validation(
             "pattern must not be empty".to_string(),
         ));
     }
 
+    if payload.pattern.len() > limits.max_pattern_bytes {
+        return Err(ApiError::Validation(format!(
+            "pattern exceeds {} bytes",
+            limits.max_pattern_bytes
+        )));
+    }
+
+    observe_text_op(metrics, "grep", &payload.text);
+    Ok(())
+}
+
+pub fn run_grep(
+    payload: GrepRequest,
+    limits: &Limits,
+    metrics: Option<&Metrics>,
+) -> Result<GrepResponse, ApiError> {
+    check_grep(&payload, limits, metrics)?;
+
     let line_numbers = payload.line_number.unwrap_or(false);
-    let lines = grep_lines(&payload.text, &payload.pattern, false, line_numbers)
-        .map_err(|e| ApiError::Internal(e.to_string()))?;
-
-    let matches = if line_numbers {
-        lines
-            .into_iter()
-            .filter_map(|line| {
-                let mut parts = line.splitn(2, ':');
-                let n = parts.next()?.parse::<usize>().ok()?;
-                let text = parts.next().unwrap_or("").to_string();
-                Some(GrepMatch { line: n, text })
-            })
-            .collect::<Vec<_>>()
-    } else {
-        lines
-            .into_iter()
-            .map(|text| GrepMatch { line: 0, text })
-            .collect::<Vec<_>>()
-    };
+    let matches = grep_iter(&payload.text, &payload.pattern, false)
+        .map(|m| grep_match(m, line_numbers))
+        .collect::<Vec<_>>();
 
     let count = matches.len();
 
-    Ok(Json(GrepResponse { matches, count }))
+    Ok(GrepResponse { matches, count })
+}
+
+fn grep_match(m: LineMatch<'_>, line_numbers: bool) -> GrepMatch {
+    GrepMatch {
+        line: if line_numbers { m.line_number } else { 0 },
+        text: m.line.to_string(),
+    }
+}
+
+/// NDJSON only if the client prefers it to JSON; a client that accepts
+/// neither gets JSON.
+fn wants_ndjson(headers: &HeaderMap) -> bool {
+    accept::preferred(headers, &[JSON, NDJSON]) == Some(NDJSON)
+}
+
+/// Streams one JSON object per match followed by a [`GrepSummary`]. The search
+/// runs on a blocking thread and waits whenever the client falls behind, so
+/// matches are never all held in memory.
+fn grep_ndjson(payload: GrepRequest) -> Response {
+    let (tx, rx) = mpsc::channel(NDJSON_BUFFER);
+
+    tokio::task::spawn_blocking(move || {
+        let line_numbers = payload.line_number.unwrap_or(false);
+        let mut count = 0;
+
+        for m in grep_iter(&payload.text, &payload.pattern, false) {
+            count += 1;
+            if tx
+                .blocking_send(ndjson_line(&grep_match(m, line_numbers)))
+                .is_err()
+            {
+                // the client disconnected
+                return;
+            }
+        }
+
+        let _ = tx.blocking_send(ndjson_line(&GrepSummary { count }));
+    });
+
+    (
+        [(CONTENT_TYPE, NDJSON)],
+        Body::from_stream(ReceiverStream::new(rx)),
+    )
+        .into_response()
+}
+
+fn ndjson_line(value: &impl Serialize) -> Result<Bytes, serde_json::Error> {
+    let mut line = serde_json::to_vec(value)?;
+    line.push(b'\n');
+    Ok(Bytes::from(line))
+}
+
+/// Runs every operation on the blocking pool, at most `batch_workers` at a
+/// time. Items are parsed one by one so a malformed item only fails itself.
+#[utoipa::path(
+    post,
+    path = "/v1/batch",
+    tag = "text",
+    request_body = Vec<BatchOp>,
+    responses(
+        (status = 200, body = BatchResponse, description = "One result or error per item, in order"),
+        (status = 413, body = ErrorBody, description = "Body, text or batch over a configured limit"),
+    )
+)]
+pub async fn batch(
+    State(limits): State<Limits>,
+    metrics: Option<Extension<Arc<Metrics>>>,
+    ApiJson(items): ApiJson<Vec<serde_json::Value>>,
+) -> Result<Json<BatchResponse>, ApiError> {
+    if items.len() > limits.max_batch_items {
+        return Err(ApiError::LimitExceeded {
+            what: "batch item count",
+            limit: limits.max_batch_items,
+        });
+    }
+
+    let results = stream::iter(items)
+        .map(|item| {
+            let metrics = metrics.as_ref().map(|Extension(m)| Arc::clone(m));
+            async move {
+                let op = serde_json::from_value::<BatchOp>(item).map_err(|e| {
+                    ApiError::InvalidBody(StatusCode::UNPROCESSABLE_ENTITY, e.to_string())
+                })?;
+                tokio::task::spawn_blocking(move || run_op(op, &limits, metrics.as_deref()))
+                    .await
+                    .map_err(|e| ApiError::Internal(e.to_string()))?
+            }
+        })
+        .buffered(limits.batch_workers.max(1))
+        .map(|result| match result {
+            Ok(output) => BatchItem::Ok(output),
+            Err(e) => BatchItem::Err {
+                error: e.into_info(),
+            },
+        })
+        .collect::<Vec<_>>()
+        .await;
+
+    Ok(Json(BatchResponse { results }))
+}
+
+fn run_op(
+    op: BatchOp,
+    limits: &Limits,
+    metrics: Option<&Metrics>,
+) -> Result<BatchOutput, ApiError> {
+    // the per-endpoint body limits bound each item's text
+    let (text, max_bytes) = match &op {
+        BatchOp::Stats(req) => (&req.text, limits.stats_body_bytes),
+        BatchOp::Uniq(req) => (&req.text, limits.uniq_body_bytes),
+        BatchOp::Grep(req) => (&req.text, limits.grep_body_bytes),
+    };
+    if text.len() > max_bytes {
+        return Err(ApiError::LimitExceeded {
+            what: "text byte count",
+            limit: max_bytes,
+        });
+    }
+
+    match op {
+        BatchOp::Stats(req) => run_stats(req, limits, metrics).map(BatchOutput::Stats),
+        BatchOp::Uniq(req) => run_uniq(req, limits, metrics).map(BatchOutput::Uniq),
+        BatchOp::Grep(req) => run_grep(req, limits, metrics).map(BatchOutput::Grep),
+    }
 }
@@ -0,0 +1,49 @@
+/// Size limits for the `/v1` text API.
+///
+/// Body limits are enforced by [`axum::extract::DefaultBodyLimit`] while the
+/// body is read, after request decompression, so an oversized upload is
+/// rejected before it is buffered in full or parsed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    pub stats_body_bytes: usize,
+    pub uniq_body_bytes: usize,
+    pub grep_body_bytes: usize,
+    /// Maximum number of lines in `text`.
+    pub max_lines: usize,
+    /// Maximum length of a grep pattern in bytes.
+    pub max_pattern_bytes: usize,
+    /// Body limit for `/v1/batch`; each item's text is also held to its
+    /// operation's body limit.
+    pub batch_body_bytes: usize,
+    pub max_batch_items: usize,
+    /// How many batch items run at once.
+    pub batch_workers: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            stats_body_bytes: 1_048_576,
+            uniq_body_bytes: 1_048_576,
+            grep_body_bytes: 1_048_576,
+            max_lines: 100_000,
+            max_pattern_bytes: 1024,
+            batch_body_bytes: 8_388_608,
+            max_batch_items: 10_000,
+            batch_workers: 8,
+        }
+    }
+}
+
+impl Limits {
+    /// These limits with every body limit, batch included, set to `bytes`.
+    pub fn with_body_limit(self, bytes: usize) -> Self {
+        Limits {
+            stats_body_bytes: bytes,
+            uniq_body_bytes: bytes,
+            grep_body_bytes: bytes,
+            batch_body_bytes: bytes,
+            ..self
+        }
+    }
+}
@@ -1,12 +1,31 @@
-use axum::{Router, routing::post};
+use axum::{Router, extract::DefaultBodyLimit};
+use tower_http::decompression::RequestDecompressionLayer;
+use utoipa_axum::{router::OpenApiRouter, router::UtoipaMethodRouterExt, routes};
 
+pub mod accept;
 pub mod errors;
+pub mod extract;
 pub mod handlers;
+pub mod limits;
 pub mod types;
 
+pub use limits::Limits;
+
 pub fn router() -> Router {
-    Router::new()
-        .route("/v1/stats", post(handlers::stats))
-        .route("/v1/uniq", post(handlers::uniq))
-        .route("/v1/grep", post(handlers::grep))
+    router_with_limits(Limits::default())
+}
+
+pub fn router_with_limits(limits: Limits) -> Router {
+    documented(limits).into()
+}
+
+/// The text API routes together with their OpenAPI operations.
+pub fn documented(limits: Limits) -> OpenApiRouter {
+    OpenApiRouter::new()
+        .routes(routes!(handlers::stats).layer(DefaultBodyLimit::max(limits.stats_body_bytes)))
+        .routes(routes!(handlers::uniq).layer(DefaultBodyLimit::max(limits.uniq_body_bytes)))
+        .routes(routes!(handlers::grep).layer(DefaultBodyLimit::max(limits.grep_body_bytes)))
+        .routes(routes!(handlers::batch).layer(DefaultBodyLimit::max(limits.batch_body_bytes)))
+        .layer(RequestDecompressionLayer::new())
+        .with_state(limits)
 }
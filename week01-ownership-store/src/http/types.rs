@@ -1,56 +1,184 @@
 use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
 
-#[derive(Deserialize)]
+use super::extract::TextPayload;
+pub use crate::error::{ErrorBody, ErrorInfo};
+
+#[derive(Deserialize, ToSchema)]
 pub struct StatsRequest {
     pub text: String,
+    pub percentiles: Option<bool>,
+}
+
+/// `StatsRequest` without `text`, for `text/plain` and multipart uploads.
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct StatsOptions {
+    pub percentiles: Option<bool>,
+}
+
+impl TextPayload for StatsRequest {
+    type Options = StatsOptions;
+
+    fn from_parts(text: String, options: StatsOptions) -> Self {
+        StatsRequest {
+            text,
+            percentiles: options.percentiles,
+        }
+    }
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct StatsResponse {
     pub lines: usize,
     pub words: usize,
     pub chars: usize,
     pub bytes: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line_lengths: Option<Distribution>,
 }
 
-#[derive(Serialize)]
-pub struct ErrorBody {
-    pub error: ErrorInfo,
+/// The wire form of [`textkit::stats::Distribution`].
+#[derive(Serialize, ToSchema)]
+pub struct Distribution {
+    pub count: usize,
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+    pub p50: f64,
+    pub p90: f64,
+    pub p95: f64,
+    pub p99: f64,
 }
 
-#[derive(Serialize)]
-pub struct ErrorInfo {
-    pub code: &'static str,
-    pub message: String,
+impl From<textkit::stats::Distribution> for Distribution {
+    fn from(d: textkit::stats::Distribution) -> Self {
+        // destructured so that a new field in textkit fails to compile here
+        let textkit::stats::Distribution {
+            count,
+            min,
+            max,
+            mean,
+            p50,
+            p90,
+            p95,
+            p99,
+        } = d;
+        Distribution {
+            count,
+            min,
+            max,
+            mean,
+            p50,
+            p90,
+            p95,
+            p99,
+        }
+    }
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct UniqRequest {
     pub text: String,
     pub all: Option<bool>,
 }
 
-#[derive(Serialize)]
+/// `UniqRequest` without `text`, for `text/plain` and multipart uploads.
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct UniqOptions {
+    pub all: Option<bool>,
+}
+
+impl TextPayload for UniqRequest {
+    type Options = UniqOptions;
+
+    fn from_parts(text: String, options: UniqOptions) -> Self {
+        UniqRequest {
+            text,
+            all: options.all,
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
 pub struct UniqResponse {
     pub text: String,
     pub removed: usize,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct GrepRequest {
     pub text: String,
     pub pattern: String,
     pub line_number: Option<bool>,
 }
 
-#[derive(Serialize)]
+/// `GrepRequest` without `text`, for `text/plain` and multipart uploads.
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct GrepOptions {
+    /// Required for `text/plain` and multipart bodies; JSON bodies carry it in the body.
+    pub pattern: String,
+    pub line_number: Option<bool>,
+}
+
+impl TextPayload for GrepRequest {
+    type Options = GrepOptions;
+
+    fn from_parts(text: String, options: GrepOptions) -> Self {
+        GrepRequest {
+            text,
+            pattern: options.pattern,
+            line_number: options.line_number,
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
 pub struct GrepMatch {
     pub line: usize,
     pub text: String,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct GrepResponse {
     pub matches: Vec<GrepMatch>,
     pub count: usize,
 }
+
+/// Last line of an NDJSON grep response.
+#[derive(Serialize, ToSchema)]
+pub struct GrepSummary {
+    pub count: usize,
+}
+
+/// One item of a `/v1/batch` request: a regular request body plus an `op` tag.
+#[derive(Deserialize, ToSchema)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum BatchOp {
+    Stats(StatsRequest),
+    Uniq(UniqRequest),
+    Grep(GrepRequest),
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(untagged)]
+pub enum BatchOutput {
+    Stats(StatsResponse),
+    Uniq(UniqResponse),
+    Grep(GrepResponse),
+}
+
+/// A batch result: the operation's usual response, or its error envelope.
+#[derive(Serialize, ToSchema)]
+#[serde(untagged)]
+pub enum BatchItem {
+    Ok(BatchOutput),
+    Err { error: ErrorInfo },
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct BatchResponse {
+    pub results: Vec<BatchItem>,
+}
@@ -1,7 +1,15 @@
+pub mod app;
+pub mod config;
 pub mod error;
+pub mod health;
+pub mod metrics;
 pub mod model;
+pub mod openapi;
 pub mod persistence;
+pub mod server;
 pub mod store;
+pub mod telemetry;
+pub mod tls;
 
 pub mod http;
 
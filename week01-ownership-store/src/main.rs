@@ -1,31 +1,152 @@
-use crate::api::state::AppState;
-use crate::storage::{file, new_store};
-
-mod api;
-mod error;
-mod model;
-mod storage;
+use clap::Parser;
+use std::process::ExitCode;
+use week01_ownership_store::api::state::AppState;
+use week01_ownership_store::app;
+use week01_ownership_store::config::{Cli, ServerConfig};
+use week01_ownership_store::server::{self, Listener, ShutdownOptions};
+use week01_ownership_store::storage::{new_store, recovery};
+use week01_ownership_store::telemetry;
+use week01_ownership_store::tls::{TlsConfig, TlsListener};
 
 #[tokio::main]
-async fn main() {
+async fn main() -> ExitCode {
+    let mut config = match ServerConfig::load(Cli::parse()) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("error: {e}");
+            return ExitCode::from(2);
+        }
+    };
+    telemetry::init(config.log_level, config.log_format);
+
     let store = new_store();
 
-    // load data.json if exists
-    if let Ok(map) = file::load_from_file("data.json").await {
-        let mut guard = store.write().await;
-        *guard = map;
+    let opened = match recovery::open(&config.data_file, config.on_load_error).await {
+        Ok(opened) => opened,
+        Err(e) => {
+            tracing::error!(
+                data_file = %config.data_file.display(),
+                error = %e,
+                "cannot load data file"
+            );
+            return ExitCode::FAILURE;
+        }
+    };
+    if let Some(err) = &opened.load_error {
+        tracing::warn!(
+            data_file = %config.data_file.display(),
+            error = %err,
+            "cannot load data file, starting empty and read-only"
+        );
+    }
+    if let Some(quarantined) = &opened.quarantined {
+        tracing::warn!(
+            data_file = %config.data_file.display(),
+            quarantined = %quarantined.display(),
+            "data file is corrupt, moved it aside"
+        );
     }
+    match &opened.backup {
+        Some(backup) => tracing::warn!(backup = %backup.display(), "loaded store from backup"),
+        None if opened.quarantined.is_some() => tracing::warn!("starting with an empty store"),
+        None => {}
+    }
+    *store.write().await = opened.map;
+    config.app.read_only |= opened.read_only;
+    config.app.load_error = opened.load_error;
 
     let state = AppState {
         store,
-        data_file: "data.json".to_string(),
+        data_file: config.data_file.to_string_lossy().into_owned(),
+        metrics: Default::default(),
+    };
+
+    let app = app::router(state.clone(), &config.app);
+
+    let tls = match config.tls.clone().map(TlsConfig::load).transpose() {
+        Ok(tls) => tls,
+        Err(e) => {
+            tracing::error!(error = %e, "cannot load TLS certificate");
+            return ExitCode::FAILURE;
+        }
     };
 
-    let app = api::router(state);
+    // before anything is logged as listening, since SIGHUP ends the process
+    // until a handler is installed
+    #[cfg(unix)]
+    if let Some(tls) = &tls {
+        match week01_ownership_store::tls::reload_on_hangup(tls.clone()) {
+            Ok(reload) => {
+                tokio::spawn(reload);
+            }
+            Err(e) => tracing::warn!(error = %e, "cannot reload the TLS certificate on SIGHUP"),
+        }
+    }
+
+    let shutdown = server::signal();
 
-    let listener = tokio::net::TcpListener::bind("127.0.0.1:3000")
-        .await
-        .expect("bind failed");
+    let listener = match tokio::net::TcpListener::bind(config.listen).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            tracing::error!(addr = %config.listen, error = %e, "cannot listen");
+            return ExitCode::FAILURE;
+        }
+    };
 
-    axum::serve(listener, app).await.expect("server failed");
+    // the bound address, in case port 0 was asked for
+    let addr = listener.local_addr().unwrap_or(config.listen);
+    tracing::info!(
+        %addr,
+        tls = tls.is_some(),
+        data_file = %config.data_file.display(),
+        "listening"
+    );
+    let mut listeners = vec![match &tls {
+        Some(tls) => Listener::Tls(TlsListener::new(listener, tls.clone())),
+        None => Listener::Tcp(listener),
+    }];
+
+    if let Some(socket) = &config.unix_socket {
+        #[cfg(unix)]
+        match socket.bind() {
+            Ok(listener) => {
+                tracing::info!(socket = %socket.path.display(), "listening");
+                listeners.push(Listener::Unix(listener));
+            }
+            Err(e) => {
+                tracing::error!(socket = %socket.path.display(), error = %e, "cannot listen");
+                return ExitCode::FAILURE;
+            }
+        }
+        #[cfg(not(unix))]
+        {
+            tracing::error!(socket = %socket.path.display(), "Unix sockets need a Unix platform");
+            return ExitCode::FAILURE;
+        }
+    }
+
+    let options = ShutdownOptions {
+        drain_timeout: config.shutdown_timeout,
+        // nothing can have changed in read-only mode
+        flush: !config.app.read_only,
+    };
+    let result = server::serve(listeners, app, state, options, shutdown).await;
+    if let Some(socket) = &config.unix_socket {
+        let _ = std::fs::remove_file(&socket.path);
+    }
+    match result {
+        Ok(summary) => {
+            tracing::info!(
+                drained = summary.drained,
+                flushed_keys = summary.flushed_keys,
+                elapsed_ms = summary.elapsed.as_millis() as u64,
+                "shut down"
+            );
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            tracing::error!(error = %e, "server failed");
+            ExitCode::FAILURE
+        }
+    }
 }
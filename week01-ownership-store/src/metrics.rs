@@ -0,0 +1,379 @@
+use axum::Router;
+use axum::extract::{MatchedPath, Request, State};
+use axum::http::header::CONTENT_TYPE;
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::Path;
+use std::sync::atomic::{AtomicU8, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use crate::api::state::AppState;
+
+/// Prometheus text exposition format.
+pub const CONTENT_TYPE_TEXT: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Upper bounds of the latency histogram buckets, in seconds.
+const LATENCY_BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct RequestKey {
+    method: String,
+    route: String,
+    status: u16,
+}
+
+#[derive(Debug, Default)]
+struct Histogram {
+    /// Non-cumulative counts per bucket of [`LATENCY_BUCKETS`], plus `+Inf`.
+    buckets: [u64; LATENCY_BUCKETS.len() + 1],
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn observe(&mut self, seconds: f64) {
+        let i = LATENCY_BUCKETS
+            .iter()
+            .position(|&le| seconds <= le)
+            .unwrap_or(LATENCY_BUCKETS.len());
+        self.buckets[i] += 1;
+        self.sum += seconds;
+        self.count += 1;
+    }
+}
+
+#[derive(Debug, Default)]
+struct TextOpStats {
+    operations: u64,
+    input_bytes: u64,
+}
+
+/// Counters and histograms of one app, shared through [`AppState`]. Store
+/// gauges are read from the store itself when rendering.
+#[derive(Debug, Default)]
+pub struct Metrics {
+    requests: Mutex<BTreeMap<RequestKey, Histogram>>,
+    text_ops: Mutex<BTreeMap<&'static str, TextOpStats>>,
+    saves: AtomicU64,
+    save_failures: AtomicU64,
+    /// Unix time of the last successful save in milliseconds; 0 if none.
+    last_save_ms: AtomicU64,
+    /// Outcome of the latest save: 0 none yet, 1 succeeded, 2 failed.
+    last_save_outcome: AtomicU8,
+}
+
+/// Store state sampled for one scrape.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct StoreSnapshot {
+    pub keys: usize,
+    pub value_bytes: usize,
+    /// `None` if the data file does not exist yet.
+    pub data_file_bytes: Option<u64>,
+}
+
+impl StoreSnapshot {
+    pub async fn take(state: &AppState) -> Self {
+        let (keys, value_bytes) = {
+            let guard = state.store.read().await;
+            let bytes = guard
+                .values()
+                .map(|v| serde_json::to_vec(v).map_or(0, |b| b.len()))
+                .sum();
+            (guard.len(), bytes)
+        };
+        let data_file_bytes = tokio::fs::metadata(Path::new(&state.data_file))
+            .await
+            .ok()
+            .map(|m| m.len());
+
+        StoreSnapshot {
+            keys,
+            value_bytes,
+            data_file_bytes,
+        }
+    }
+}
+
+impl Metrics {
+    pub fn observe_request(&self, method: &str, route: &str, status: u16, seconds: f64) {
+        let key = RequestKey {
+            method: method.to_string(),
+            route: route.to_string(),
+            status,
+        };
+        let mut requests = self.requests.lock().unwrap_or_else(|e| e.into_inner());
+        requests.entry(key).or_default().observe(seconds);
+    }
+
+    /// Counts one text operation (`stats`, `uniq` or `grep`) over `bytes` of input.
+    pub fn observe_text_op(&self, op: &'static str, bytes: usize) {
+        let mut ops = self.text_ops.lock().unwrap_or_else(|e| e.into_inner());
+        let stats = ops.entry(op).or_default();
+        stats.operations += 1;
+        stats.input_bytes += bytes as u64;
+    }
+
+    /// Records the outcome of writing the data file.
+    pub fn observe_save(&self, ok: bool) {
+        if ok {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_millis() as u64);
+            self.saves.fetch_add(1, Ordering::Relaxed);
+            self.last_save_ms.store(now, Ordering::Relaxed);
+            self.last_save_outcome.store(1, Ordering::Relaxed);
+        } else {
+            self.save_failures.fetch_add(1, Ordering::Relaxed);
+            self.last_save_outcome.store(2, Ordering::Relaxed);
+        }
+    }
+
+    /// Whether the latest save succeeded; `None` before the first one.
+    pub fn last_save_succeeded(&self) -> Option<bool> {
+        match self.last_save_outcome.load(Ordering::Relaxed) {
+            1 => Some(true),
+            2 => Some(false),
+            _ => None,
+        }
+    }
+
+    /// Renders everything in the Prometheus text format.
+    pub fn render(&self, store: &StoreSnapshot) -> String {
+        let mut out = String::new();
+
+        out.push_str("# HELP http_requests_total Requests handled, by method, route and status.\n");
+        out.push_str("# TYPE http_requests_total counter\n");
+        let requests = self.requests.lock().unwrap_or_else(|e| e.into_inner());
+        for (key, hist) in requests.iter() {
+            let _ = writeln!(out, "http_requests_total{{{}}} {}", labels(key), hist.count);
+        }
+
+        out.push_str(
+            "# HELP http_request_duration_seconds Request latency, by method, route and status.\n",
+        );
+        out.push_str("# TYPE http_request_duration_seconds histogram\n");
+        for (key, hist) in requests.iter() {
+            let labels = labels(key);
+            let mut cumulative = 0;
+            for (i, count) in hist.buckets.iter().enumerate() {
+                cumulative += count;
+                let le = LATENCY_BUCKETS
+                    .get(i)
+                    .map_or("+Inf".to_string(), |le| le.to_string());
+                let _ = writeln!(
+                    out,
+                    "http_request_duration_seconds_bucket{{{labels},le=\"{le}\"}} {cumulative}"
+                );
+            }
+            let _ = writeln!(
+                out,
+                "http_request_duration_seconds_sum{{{labels}}} {}",
+                hist.sum
+            );
+            let _ = writeln!(
+                out,
+                "http_request_duration_seconds_count{{{labels}}} {}",
+                hist.count
+            );
+        }
+        drop(requests);
+
+        let ops = self.text_ops.lock().unwrap_or_else(|e| e.into_inner());
+        out.push_str("# HELP text_operations_total Text operations run, batch items included.\n");
+        out.push_str("# TYPE text_operations_total counter\n");
+        for (op, stats) in ops.iter() {
+            let _ = writeln!(
+                out,
+                "text_operations_total{{op=\"{op}\"}} {}",
+                stats.operations
+            );
+        }
+        out.push_str("# HELP text_input_bytes_total Bytes of text given to text operations.\n");
+        out.push_str("# TYPE text_input_bytes_total counter\n");
+        for (op, stats) in ops.iter() {
+            let _ = writeln!(
+                out,
+                "text_input_bytes_total{{op=\"{op}\"}} {}",
+                stats.input_bytes
+            );
+        }
+        drop(ops);
+
+        gauge(
+            &mut out,
+            "store_keys",
+            "Keys in the store.",
+            store.keys as f64,
+        );
+        gauge(
+            &mut out,
+            "store_value_bytes",
+            "Total size of the stored values as compact JSON.",
+            store.value_bytes as f64,
+        );
+        gauge(
+            &mut out,
+            "store_data_file_bytes",
+            "Size of the data file; 0 if it does not exist yet.",
+            store.data_file_bytes.unwrap_or(0) as f64,
+        );
+        gauge(
+            &mut out,
+            "store_last_save_timestamp_seconds",
+            "Unix time of the last successful save; 0 if none yet.",
+            self.last_save_ms.load(Ordering::Relaxed) as f64 / 1000.0,
+        );
+        counter(
+            &mut out,
+            "store_saves_total",
+            "Successful saves of the data file.",
+            self.saves.load(Ordering::Relaxed),
+        );
+        counter(
+            &mut out,
+            "store_save_failures_total",
+            "Failed saves of the data file.",
+            self.save_failures.load(Ordering::Relaxed),
+        );
+
+        out
+    }
+}
+
+fn gauge(out: &mut String, name: &str, help: &str, value: f64) {
+    let _ = writeln!(
+        out,
+        "# HELP {name} {help}\n# TYPE {name} gauge\n{name} {value}"
+    );
+}
+
+fn counter(out: &mut String, name: &str, help: &str, value: u64) {
+    let _ = writeln!(
+        out,
+        "# HELP {name} {help}\n# TYPE {name} counter\n{name} {value}"
+    );
+}
+
+fn labels(key: &RequestKey) -> String {
+    format!(
+        "method=\"{}\",route=\"{}\",status=\"{}\"",
+        escape(&key.method),
+        escape(&key.route),
+        key.status
+    )
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Counts every request and its latency under its matched route, and hands
+/// `metrics` to the handlers as a request extension.
+pub async fn track_requests(
+    State(metrics): State<Arc<Metrics>>,
+    mut req: Request,
+    next: Next,
+) -> Response {
+    req.extensions_mut().insert(Arc::clone(&metrics));
+    let method = req.method().to_string();
+    let route = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or("<unmatched>", |path| path.as_str())
+        .to_string();
+
+    let started = Instant::now();
+    let resp = next.run(req).await;
+    metrics.observe_request(
+        &method,
+        &route,
+        resp.status().as_u16(),
+        started.elapsed().as_secs_f64(),
+    );
+    resp
+}
+
+async fn serve_metrics(State(state): State<AppState>) -> Response {
+    let snapshot = StoreSnapshot::take(&state).await;
+    (
+        [(CONTENT_TYPE, CONTENT_TYPE_TEXT)],
+        state.metrics.render(&snapshot),
+    )
+        .into_response()
+}
+
+/// Serves `/metrics` for the store in `state`.
+pub fn router(state: AppState) -> Router {
+    Router::new()
+        .route("/metrics", get(serve_metrics))
+        .with_state(state)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Metrics, StoreSnapshot};
+
+    #[test]
+    fn renders_cumulative_buckets_per_label_set() {
+        let metrics = Metrics::default();
+        metrics.observe_request("GET", "/kv/{key}", 200, 0.003);
+        metrics.observe_request("GET", "/kv/{key}", 200, 0.2);
+        metrics.observe_request("GET", "/kv/{key}", 404, 20.0);
+
+        let out = metrics.render(&StoreSnapshot::default());
+
+        let ok = r#"method="GET",route="/kv/{key}",status="200""#;
+        assert!(out.contains(&format!("http_requests_total{{{ok}}} 2\n")));
+        assert!(out.contains(&format!(
+            "http_request_duration_seconds_bucket{{{ok},le=\"0.005\"}} 1\n"
+        )));
+        assert!(out.contains(&format!(
+            "http_request_duration_seconds_bucket{{{ok},le=\"0.25\"}} 2\n"
+        )));
+        assert!(out.contains(&format!(
+            "http_request_duration_seconds_bucket{{{ok},le=\"+Inf\"}} 2\n"
+        )));
+        let missing = r#"method="GET",route="/kv/{key}",status="404""#;
+        assert!(out.contains(&format!(
+            "http_request_duration_seconds_bucket{{{missing},le=\"10\"}} 0\n"
+        )));
+        assert!(out.contains(&format!(
+            "http_request_duration_seconds_count{{{missing}}} 1\n"
+        )));
+    }
+
+    #[test]
+    fn renders_store_gauges_and_save_counters() {
+        let metrics = Metrics::default();
+        metrics.observe_save(true);
+        metrics.observe_save(false);
+        metrics.observe_text_op("grep", 10);
+        metrics.observe_text_op("grep", 5);
+
+        let out = metrics.render(&StoreSnapshot {
+            keys: 3,
+            value_bytes: 42,
+            data_file_bytes: Some(100),
+        });
+
+        assert!(out.contains("store_keys 3\n"));
+        assert!(out.contains("store_value_bytes 42\n"));
+        assert!(out.contains("store_data_file_bytes 100\n"));
+        assert!(out.contains("store_saves_total 1\n"));
+        assert!(out.contains("store_save_failures_total 1\n"));
+        assert!(out.contains("text_operations_total{op=\"grep\"} 2\n"));
+        assert!(out.contains("text_input_bytes_total{op=\"grep\"} 15\n"));
+        assert!(!out.contains("store_last_save_timestamp_seconds 0\n"));
+        assert_eq!(Some(false), metrics.last_save_succeeded());
+        assert_eq!(None, Metrics::default().last_save_succeeded());
+    }
+}
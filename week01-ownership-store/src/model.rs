@@ -1,12 +1,13 @@
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use utoipa::ToSchema;
 
 #[allow(dead_code)]
 pub struct Record {
     pub id: u64,
     pub payload: Vec<u8>,
 }
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, ToSchema)]
 pub struct KvPair {
     pub key: String,
     pub value: Value,
@@ -0,0 +1,49 @@
+use axum::{Json, Router, routing::get};
+use utoipa::OpenApi;
+use utoipa_scalar::{Scalar, Servable};
+
+use crate::{api, http};
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "week01-ownership-store",
+        description = "Text processing (`/v1`) and key-value store (`/kv`) API"
+    ),
+    tags(
+        (name = "text", description = "Text statistics, uniq and grep backed by textkit"),
+        (name = "kv", description = "JSON key-value store persisted to a file")
+    )
+)]
+struct ApiDoc;
+
+/// The OpenAPI 3 document for both [`http::router`] and [`api::router`],
+/// generated from the routes themselves.
+pub fn openapi() -> utoipa::openapi::OpenApi {
+    base()
+        .merge_from(http::documented(http::Limits::default()).into_openapi())
+        .merge_from(api::documented().into_openapi())
+}
+
+/// The document's info and tags, without any paths.
+pub fn base() -> utoipa::openapi::OpenApi {
+    ApiDoc::openapi()
+}
+
+/// Serves the document at `/openapi.json` and interactive docs at `/docs`.
+pub fn router() -> Router {
+    serve(openapi())
+}
+
+/// Like [`router`], for a document built elsewhere, e.g. with prefixed paths.
+pub fn serve(doc: utoipa::openapi::OpenApi) -> Router {
+    Router::new()
+        .route(
+            "/openapi.json",
+            get({
+                let doc = doc.clone();
+                move || async move { Json(doc) }
+            }),
+        )
+        .merge(Scalar::with_url("/docs", doc))
+}
@@ -0,0 +1,256 @@
+use axum::Router;
+use std::future::Future;
+use std::io;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use tokio::net::TcpListener;
+use tokio::sync::watch;
+use tokio::task::JoinSet;
+
+use crate::api::state::AppState;
+use crate::error::ApiError;
+use crate::storage::file;
+use crate::tls::TlsListener;
+
+pub const DEFAULT_DRAIN_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Copy)]
+pub struct ShutdownOptions {
+    /// How long in-flight requests may take once shutdown starts.
+    pub drain_timeout: Duration,
+    /// Write the store to its data file after draining.
+    pub flush: bool,
+}
+
+impl Default for ShutdownOptions {
+    fn default() -> Self {
+        ShutdownOptions {
+            drain_timeout: DEFAULT_DRAIN_TIMEOUT,
+            flush: true,
+        }
+    }
+}
+
+/// What happened during shutdown.
+#[derive(Debug)]
+pub struct ShutdownSummary {
+    /// `false` if the drain timeout expired before every request finished.
+    pub drained: bool,
+    /// Number of keys written by the final flush, if it ran.
+    pub flushed_keys: Option<usize>,
+    /// Time from the shutdown signal to the end of the flush.
+    pub elapsed: Duration,
+}
+
+/// Resolves on SIGINT (Ctrl-C) or, on Unix, SIGTERM. On Unix the handlers are
+/// installed right away rather than on the first poll, so call this before
+/// announcing that the server is up.
+pub fn signal() -> impl Future<Output = ()> {
+    #[cfg(unix)]
+    let (interrupt, terminate) = {
+        use tokio::signal::unix::{SignalKind, signal};
+        (
+            signal(SignalKind::interrupt()).ok(),
+            signal(SignalKind::terminate()).ok(),
+        )
+    };
+
+    async move {
+        #[cfg(unix)]
+        {
+            async fn recv(sig: Option<tokio::signal::unix::Signal>) {
+                match sig {
+                    Some(mut sig) => {
+                        sig.recv().await;
+                    }
+                    None => std::future::pending::<()>().await,
+                }
+            }
+            tokio::select! {
+                _ = recv(interrupt) => {}
+                _ = recv(terminate) => {}
+            }
+        }
+        #[cfg(not(unix))]
+        if tokio::signal::ctrl_c().await.is_err() {
+            std::future::pending::<()>().await;
+        }
+    }
+}
+
+/// A bound socket that [`serve`] accepts connections on.
+pub enum Listener {
+    Tcp(TcpListener),
+    Tls(TlsListener),
+    #[cfg(unix)]
+    Unix(tokio::net::UnixListener),
+}
+
+impl From<TcpListener> for Listener {
+    fn from(listener: TcpListener) -> Self {
+        Listener::Tcp(listener)
+    }
+}
+
+impl Listener {
+    /// Serves `app` until `stop` turns `true`, then stops accepting and
+    /// returns once every open connection is done.
+    async fn run(self, app: Router, mut stop: watch::Receiver<bool>) -> io::Result<()> {
+        let stopped = async move {
+            let _ = stop.wait_for(|stop| *stop).await;
+        };
+        match self {
+            Listener::Tcp(listener) => {
+                axum::serve(listener, app)
+                    .with_graceful_shutdown(stopped)
+                    .await
+            }
+            Listener::Tls(listener) => {
+                axum::serve(listener, app)
+                    .with_graceful_shutdown(stopped)
+                    .await
+            }
+            #[cfg(unix)]
+            Listener::Unix(listener) => {
+                axum::serve(listener, app)
+                    .with_graceful_shutdown(stopped)
+                    .await
+            }
+        }
+    }
+}
+
+/// Where to listen for local clients, such as a sidecar, without a TCP port.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnixSocket {
+    pub path: PathBuf,
+    /// Permission bits of the socket file, e.g. `0o660`.
+    pub mode: u32,
+}
+
+#[cfg(unix)]
+impl UnixSocket {
+    /// Binds the socket and sets its permissions. A socket file left behind
+    /// by a previous run is replaced, unless a server still answers on it;
+    /// any other file at the path is an error.
+    ///
+    /// The socket is bound in a private directory next to `path` and only
+    /// renamed into place once it has its mode, so nobody can connect while
+    /// it still has the permissions of the umask.
+    pub fn bind(&self) -> io::Result<tokio::net::UnixListener> {
+        use std::os::unix::fs::{DirBuilderExt, FileTypeExt, PermissionsExt};
+
+        match std::fs::symlink_metadata(&self.path) {
+            Ok(meta) if meta.file_type().is_socket() => {
+                if std::os::unix::net::UnixStream::connect(&self.path).is_ok() {
+                    return Err(io::Error::new(
+                        io::ErrorKind::AddrInUse,
+                        "another server is listening on it",
+                    ));
+                }
+                std::fs::remove_file(&self.path)?;
+            }
+            Ok(_) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::AlreadyExists,
+                    "a file that is not a socket is in the way",
+                ));
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+
+        let name = self.path.file_name().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "socket path has no file name")
+        })?;
+        let mut private = self.path.clone();
+        private.set_file_name(format!(
+            ".{}.{}",
+            name.to_string_lossy(),
+            std::process::id()
+        ));
+        // left over from a crashed run with the same pid
+        if private.exists() {
+            std::fs::remove_dir_all(&private)?;
+        }
+        std::fs::DirBuilder::new().mode(0o700).create(&private)?;
+
+        let staged = private.join("s");
+        let bound = tokio::net::UnixListener::bind(&staged).and_then(|listener| {
+            std::fs::set_permissions(&staged, std::fs::Permissions::from_mode(self.mode))?;
+            std::fs::rename(&staged, &self.path)?;
+            Ok(listener)
+        });
+        let cleaned = std::fs::remove_dir_all(&private);
+        let listener = bound?;
+        cleaned?;
+        Ok(listener)
+    }
+}
+
+/// Serves `app` on every listener until `shutdown` resolves, then stops
+/// accepting connections, waits up to `drain_timeout` for in-flight requests
+/// and finally syncs the store to its data file.
+///
+/// Returns an error if a listener fails before shutdown; a failed flush is
+/// reported as an error as well, since data may have been lost.
+pub async fn serve(
+    listeners: impl IntoIterator<Item = Listener>,
+    app: Router,
+    state: AppState,
+    options: ShutdownOptions,
+    shutdown: impl Future<Output = ()>,
+) -> io::Result<ShutdownSummary> {
+    let (stop_tx, stop_rx) = watch::channel(false);
+    let mut servers = JoinSet::new();
+    for listener in listeners {
+        servers.spawn(listener.run(app.clone(), stop_rx.clone()));
+    }
+
+    tokio::select! {
+        Some(result) = servers.join_next() => {
+            result.map_err(io::Error::other)??;
+            return Err(io::Error::other("server stopped unexpectedly"));
+        }
+        () = shutdown => {}
+    }
+    let started = Instant::now();
+    let _ = stop_tx.send(true);
+
+    let drain = tokio::time::timeout(options.drain_timeout, async {
+        while let Some(result) = servers.join_next().await {
+            result.map_err(io::Error::other)??;
+        }
+        io::Result::Ok(())
+    })
+    .await;
+    let drained = match drain {
+        Ok(result) => {
+            result?;
+            true
+        }
+        Err(_) => {
+            servers.abort_all();
+            false
+        }
+    };
+    let flushed_keys = if options.flush {
+        // waits for any write still holding the lock
+        let guard = state.store.write().await;
+        let result = file::save_to_file(&state.data_file, &guard, true).await;
+        state.metrics.observe_save(result.is_ok());
+        result.map_err(|e| match e {
+            ApiError::Io(err) => err,
+            other => io::Error::other(other.into_info().message),
+        })?;
+        Some(guard.len())
+    } else {
+        None
+    };
+
+    Ok(ShutdownSummary {
+        drained,
+        flushed_keys,
+        elapsed: started.elapsed(),
+    })
+}
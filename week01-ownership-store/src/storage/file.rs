@@ -1,34 +1,80 @@
 use serde_json::Value;
 use std::collections::HashMap;
-use std::path::Path;
+use std::io;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
 use tokio::fs;
+use tokio::io::AsyncWriteExt;
 
 use crate::error::ApiError;
-pub async fn load_from_file(path: impl AsRef<Path>) -> Result<HashMap<String, Value>, ApiError> {
+
+/// Why the data file could not be loaded.
+#[derive(Debug, Error)]
+pub enum LoadError {
+    /// The file could not be read at all.
+    #[error("cannot read {}: {source}", path.display())]
+    Io { path: PathBuf, source: io::Error },
+    /// The file was read but is not a JSON object of values, e.g. because it
+    /// is truncated or holds bytes that are not UTF-8.
+    #[error("{} is corrupt: {source}", path.display())]
+    Corrupt {
+        path: PathBuf,
+        source: serde_json::Error,
+    },
+}
+
+pub async fn load_from_file(path: impl AsRef<Path>) -> Result<HashMap<String, Value>, LoadError> {
     let file = path.as_ref();
 
-    let content = match fs::read_to_string(file).await {
+    // bytes, so that invalid UTF-8 is reported as corruption rather than
+    // as a read error
+    let content = match fs::read(file).await {
         Ok(content) => content,
-        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(HashMap::new()),
-        Err(err) => return Err(ApiError::Io(err)),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(HashMap::new()),
+        Err(source) => {
+            return Err(LoadError::Io {
+                path: file.to_path_buf(),
+                source,
+            });
+        }
     };
 
-    let map =
-        serde_json::from_str::<HashMap<String, Value>>(&content).map_err(ApiError::InvalidJson)?;
-
-    Ok(map)
+    serde_json::from_slice(&content).map_err(|source| LoadError::Corrupt {
+        path: file.to_path_buf(),
+        source,
+    })
 }
 
+/// Replaces the data file through a temporary file, so readers never see a
+/// partial write. If `durable`, the file and its directory are also synced
+/// to disk before returning, so the data survives a crash or power loss.
 pub async fn save_to_file(
     path: impl AsRef<Path>,
     map: &HashMap<String, Value>,
+    durable: bool,
 ) -> Result<(), ApiError> {
     let file = path.as_ref();
     let tmp_file = file.with_extension("tmp");
 
     let json = serde_json::to_string_pretty(&map).map_err(ApiError::InvalidJson)?;
-    fs::write(&tmp_file, json).await.map_err(ApiError::Io)?;
-    fs::rename(&tmp_file, path).await.map_err(ApiError::Io)?;
+    let mut tmp = fs::File::create(&tmp_file).await.map_err(ApiError::Io)?;
+    tmp.write_all(json.as_bytes()).await.map_err(ApiError::Io)?;
+    if durable {
+        tmp.sync_all().await.map_err(ApiError::Io)?;
+    }
+    drop(tmp);
+    fs::rename(&tmp_file, file).await.map_err(ApiError::Io)?;
+
+    // make the rename itself durable
+    #[cfg(unix)]
+    if durable && let Some(dir) = file.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::File::open(dir)
+            .await
+            .map_err(ApiError::Io)?
+            .sync_all()
+            .await
+            .map_err(ApiError::Io)?;
+    }
     Ok(())
 }
 
@@ -47,6 +93,16 @@ mod tests {
         assert!(map.is_empty());
     }
 
+    #[tokio::test]
+    async fn invalid_utf8_is_corrupt_not_io() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("data.json");
+        std::fs::write(&path, b"\xff\xfe{").unwrap();
+
+        let err = load_from_file(&path).await.unwrap_err();
+        assert!(matches!(err, LoadError::Corrupt { .. }), "{err}");
+    }
+
     #[tokio::test]
     async fn save_then_load_roundtrip() {
         let dir = tempdir().unwrap();
@@ -55,9 +111,24 @@ mod tests {
         let mut map = HashMap::new();
         map.insert("k1".to_string(), json!("v1"));
 
-        save_to_file(&path, &map).await.unwrap();
+        save_to_file(&path, &map, false).await.unwrap();
         let loaded = load_from_file(&path).await.unwrap();
 
         assert_eq!(loaded.get("k1"), Some(&json!("v1")));
     }
+
+    #[tokio::test]
+    async fn durable_save_replaces_file_and_leaves_no_tmp() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("data.json");
+        std::fs::write(&path, "{}").unwrap();
+
+        let mut map = HashMap::new();
+        map.insert("k1".to_string(), json!(1));
+
+        save_to_file(&path, &map, true).await.unwrap();
+
+        assert_eq!(load_from_file(&path).await.unwrap(), map);
+        assert!(!path.with_extension("tmp").exists());
+    }
 }
@@ -10,6 +10,7 @@ pub fn new_store() -> Store {
 }
 
 pub mod file;
+pub mod recovery;
 
 #[cfg(test)]
 mod tests {
@@ -0,0 +1,329 @@
+use serde_json::Value;
+use std::collections::HashMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+
+use super::file::{self, LoadError};
+
+/// What to do when the data file exists but cannot be loaded.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LoadErrorPolicy {
+    /// Refuse to start.
+    #[default]
+    Fail,
+    /// Start with an empty store that rejects writes, so the file is never
+    /// overwritten, and report not ready.
+    ReadOnly,
+    /// Move a corrupt file aside to `<name>.corrupt-<unix time>` and start
+    /// with an empty store.
+    Quarantine,
+    /// Move a corrupt file aside like [`LoadErrorPolicy::Quarantine`] and load
+    /// the newest backup (`<name>.bak-*`) that is valid.
+    Backup,
+}
+
+impl FromStr for LoadErrorPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "fail" => Ok(LoadErrorPolicy::Fail),
+            "read-only" => Ok(LoadErrorPolicy::ReadOnly),
+            "quarantine" => Ok(LoadErrorPolicy::Quarantine),
+            "backup" => Ok(LoadErrorPolicy::Backup),
+            _ => Err(format!(
+                "unknown load error policy: {s} (expected fail, read-only, quarantine or backup)"
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum RecoveryError {
+    #[error(transparent)]
+    Load(#[from] LoadError),
+    #[error("{} is corrupt and has no valid backup", path.display())]
+    NoBackup { path: PathBuf },
+    #[error("cannot move {} aside: {source}", path.display())]
+    Quarantine { path: PathBuf, source: io::Error },
+    #[error("cannot look for backups in {}: {source}", dir.display())]
+    Backups { dir: PathBuf, source: io::Error },
+}
+
+/// The store contents to start with.
+#[derive(Debug, Default)]
+pub struct Opened {
+    pub map: HashMap<String, Value>,
+    /// Why the data file could not be loaded, if the policy let the server
+    /// start anyway.
+    pub load_error: Option<String>,
+    /// The store must not be written to.
+    pub read_only: bool,
+    /// Where the corrupt data file was moved to.
+    pub quarantined: Option<PathBuf>,
+    /// The backup the store was loaded from.
+    pub backup: Option<PathBuf>,
+}
+
+/// Loads `path`, applying `policy` if that fails. A missing file is an empty
+/// store, not an error.
+///
+/// Quarantine and backup only apply to corrupt files, ones that are not valid
+/// UTF-8 JSON; read errors, such as a permission problem, always fail since
+/// moving the file would not help.
+pub async fn open(path: &Path, policy: LoadErrorPolicy) -> Result<Opened, RecoveryError> {
+    let err = match file::load_from_file(path).await {
+        Ok(map) => {
+            return Ok(Opened {
+                map,
+                ..Opened::default()
+            });
+        }
+        Err(err) => err,
+    };
+
+    match (policy, &err) {
+        (LoadErrorPolicy::Fail, _) => Err(err.into()),
+        (LoadErrorPolicy::ReadOnly, _) => Ok(Opened {
+            load_error: Some(err.to_string()),
+            read_only: true,
+            ..Opened::default()
+        }),
+        (_, LoadError::Io { .. }) => Err(err.into()),
+        (LoadErrorPolicy::Quarantine, _) => Ok(Opened {
+            quarantined: Some(quarantine(path).await?),
+            ..Opened::default()
+        }),
+        (LoadErrorPolicy::Backup, _) => {
+            let (backup, map) =
+                newest_valid_backup(path)
+                    .await?
+                    .ok_or_else(|| RecoveryError::NoBackup {
+                        path: path.to_path_buf(),
+                    })?;
+            Ok(Opened {
+                map,
+                quarantined: Some(quarantine(path).await?),
+                backup: Some(backup),
+                ..Opened::default()
+            })
+        }
+    }
+}
+
+/// `path` with `suffix` appended to its file name.
+fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+/// Renames `path` to `<name>.corrupt-<unix time>`, adding a counter if a file
+/// of that name already exists.
+async fn quarantine(path: &Path) -> Result<PathBuf, RecoveryError> {
+    let failed = |source| RecoveryError::Quarantine {
+        path: path.to_path_buf(),
+        source,
+    };
+    let ts = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+
+    let mut target = sibling(path, &format!(".corrupt-{ts}"));
+    let mut n = 1;
+    while tokio::fs::try_exists(&target).await.map_err(failed)? {
+        target = sibling(path, &format!(".corrupt-{ts}-{n}"));
+        n += 1;
+    }
+
+    tokio::fs::rename(path, &target).await.map_err(failed)?;
+    Ok(target)
+}
+
+/// Tries the `<name>.bak-*` files next to `path`, newest first, and returns
+/// the first that loads.
+async fn newest_valid_backup(
+    path: &Path,
+) -> Result<Option<(PathBuf, HashMap<String, Value>)>, RecoveryError> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let prefix = sibling(Path::new(path.file_name().unwrap_or_default()), ".bak-");
+    let prefix = prefix.to_string_lossy();
+
+    let mut backups = Vec::new();
+    let failed = |source| RecoveryError::Backups {
+        dir: dir.to_path_buf(),
+        source,
+    };
+    let mut entries = tokio::fs::read_dir(dir).await.map_err(failed)?;
+    while let Some(entry) = entries.next_entry().await.map_err(failed)? {
+        if !entry.file_name().to_string_lossy().starts_with(&*prefix) {
+            continue;
+        }
+        let modified = match entry.metadata().await.and_then(|m| m.modified()) {
+            Ok(modified) => modified,
+            Err(_) => continue,
+        };
+        backups.push((modified, entry.path()));
+    }
+    // newest first; names break ties so the order is stable
+    backups.sort_by(|a, b| b.cmp(a));
+
+    for (_, backup) in backups {
+        if let Ok(map) = file::load_from_file(&backup).await {
+            return Ok(Some((backup, map)));
+        }
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LoadErrorPolicy, RecoveryError, open};
+    use serde_json::json;
+    use std::path::Path;
+    use std::time::{Duration, SystemTime};
+    use tempfile::tempdir;
+
+    fn write_aged(path: &Path, content: &str, age_secs: u64) {
+        std::fs::write(path, content).unwrap();
+        let file = std::fs::File::options().write(true).open(path).unwrap();
+        file.set_modified(SystemTime::now() - Duration::from_secs(age_secs))
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn corrupt_file_fails_by_default() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("data.json");
+        std::fs::write(&path, "{not json").unwrap();
+
+        assert!(open(&path, LoadErrorPolicy::default()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn read_only_policy_starts_empty_and_keeps_the_file() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("data.json");
+        std::fs::write(&path, "{not json").unwrap();
+
+        let opened = open(&path, LoadErrorPolicy::ReadOnly).await.unwrap();
+
+        assert!(opened.map.is_empty());
+        assert!(opened.read_only);
+        assert!(opened.load_error.is_some());
+        assert_eq!("{not json", std::fs::read_to_string(&path).unwrap());
+    }
+
+    #[tokio::test]
+    async fn missing_file_is_not_a_load_error() {
+        let dir = tempdir().unwrap();
+
+        let opened = open(&dir.path().join("data.json"), LoadErrorPolicy::Fail)
+            .await
+            .unwrap();
+
+        assert!(opened.map.is_empty());
+        assert!(opened.load_error.is_none());
+        assert!(!opened.read_only);
+    }
+
+    #[tokio::test]
+    async fn quarantine_moves_the_file_aside_and_starts_empty() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("data.json");
+        std::fs::write(&path, "{not json").unwrap();
+
+        let first = open(&path, LoadErrorPolicy::Quarantine).await.unwrap();
+        std::fs::write(&path, "[]").unwrap();
+        let second = open(&path, LoadErrorPolicy::Quarantine).await.unwrap();
+
+        assert!(first.map.is_empty());
+        assert!(!first.read_only);
+        assert!(!path.exists());
+        let first = first.quarantined.unwrap();
+        let second = second.quarantined.unwrap();
+        assert_ne!(first, second);
+        let name = first.file_name().unwrap().to_string_lossy().into_owned();
+        assert!(name.starts_with("data.json.corrupt-"), "{name}");
+        assert_eq!("{not json", std::fs::read_to_string(&first).unwrap());
+        assert_eq!("[]", std::fs::read_to_string(&second).unwrap());
+    }
+
+    #[tokio::test]
+    async fn invalid_utf8_is_quarantined() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("data.json");
+        std::fs::write(&path, b"\xff\xfe{").unwrap();
+
+        let opened = open(&path, LoadErrorPolicy::Quarantine).await.unwrap();
+
+        assert!(opened.map.is_empty());
+        let quarantined = opened.quarantined.unwrap();
+        assert_eq!(
+            b"\xff\xfe{".as_slice(),
+            std::fs::read(&quarantined).unwrap()
+        );
+        assert!(!path.exists());
+    }
+
+    #[tokio::test]
+    async fn unreadable_file_fails_even_with_quarantine() {
+        let dir = tempdir().unwrap();
+        // a directory cannot be read as a file
+        let path = dir.path().join("data.json");
+        std::fs::create_dir(&path).unwrap();
+
+        let err = open(&path, LoadErrorPolicy::Quarantine).await.unwrap_err();
+
+        assert!(matches!(err, RecoveryError::Load(_)), "{err}");
+        assert!(path.is_dir());
+    }
+
+    #[tokio::test]
+    async fn backup_loads_the_newest_valid_backup() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("data.json");
+        std::fs::write(&path, "{not json").unwrap();
+        write_aged(&dir.path().join("data.json.bak-1"), r#"{"v": 1}"#, 300);
+        write_aged(&dir.path().join("data.json.bak-2"), r#"{"v": 2}"#, 200);
+        // newest, but corrupt too
+        write_aged(&dir.path().join("data.json.bak-3"), "{", 100);
+        write_aged(&dir.path().join("other.json.bak-9"), r#"{"v": 9}"#, 0);
+
+        let opened = open(&path, LoadErrorPolicy::Backup).await.unwrap();
+
+        assert_eq!(Some(&json!(2)), opened.map.get("v"));
+        assert_eq!(dir.path().join("data.json.bak-2"), opened.backup.unwrap());
+        assert!(opened.quarantined.is_some());
+        assert!(!path.exists());
+    }
+
+    #[tokio::test]
+    async fn backup_without_a_valid_backup_fails_and_keeps_the_file() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("data.json");
+        std::fs::write(&path, "{not json").unwrap();
+        std::fs::write(dir.path().join("data.json.bak-1"), "nope").unwrap();
+
+        let err = open(&path, LoadErrorPolicy::Backup).await.unwrap_err();
+
+        assert!(matches!(err, RecoveryError::NoBackup { .. }), "{err}");
+        assert_eq!("{not json", std::fs::read_to_string(&path).unwrap());
+    }
+
+    #[test]
+    fn policy_parses() {
+        assert_eq!(
+            LoadErrorPolicy::Quarantine,
+            "quarantine".parse::<LoadErrorPolicy>().unwrap()
+        );
+        assert_eq!(LoadErrorPolicy::Backup, "backup".parse().unwrap());
+        assert!("ignore".parse::<LoadErrorPolicy>().is_err());
+    }
+}
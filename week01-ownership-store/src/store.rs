@@ -151,6 +151,7 @@ fn remove_transfers_ownership() {
 }
 
 #[test]
+#[allow(clippy::bool_assert_comparison)]
 fn contains_true_false() {
     let mut s = Store::new();
 
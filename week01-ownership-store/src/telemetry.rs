@@ -0,0 +1,177 @@
+use axum::extract::{MatchedPath, Request};
+use axum::http::HeaderValue;
+use axum::middleware::Next;
+use axum::response::Response;
+use std::io::IsTerminal;
+use std::str::FromStr;
+use std::time::Instant;
+use tracing::Instrument;
+use tracing_subscriber::filter::LevelFilter;
+
+use crate::config::LogLevel;
+use crate::error::REQUEST_ID_HEADER;
+
+/// Longest client-supplied request id that is kept; longer ones are replaced.
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+/// How log lines are written to stderr.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LogFormat {
+    /// Human-readable lines.
+    #[default]
+    Pretty,
+    /// One JSON object per line, for log collectors.
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pretty" => Ok(LogFormat::Pretty),
+            "json" => Ok(LogFormat::Json),
+            _ => Err(format!("unknown log format: {s} (expected pretty or json)")),
+        }
+    }
+}
+
+impl From<LogLevel> for LevelFilter {
+    fn from(level: LogLevel) -> Self {
+        match level {
+            LogLevel::Error => LevelFilter::ERROR,
+            LogLevel::Warn => LevelFilter::WARN,
+            LogLevel::Info => LevelFilter::INFO,
+            LogLevel::Debug => LevelFilter::DEBUG,
+            LogLevel::Trace => LevelFilter::TRACE,
+        }
+    }
+}
+
+/// Installs the global subscriber. Call once, before serving.
+pub fn init(level: LogLevel, format: LogFormat) {
+    let builder = tracing_subscriber::fmt()
+        .with_max_level(LevelFilter::from(level))
+        .with_ansi(std::io::stderr().is_terminal())
+        .with_writer(std::io::stderr);
+
+    match format {
+        LogFormat::Pretty => builder.init(),
+        LogFormat::Json => builder.json().flatten_event(true).init(),
+    }
+}
+
+/// The id of the current request, also available as a request extension.
+#[derive(Debug, Clone)]
+pub struct RequestId(pub String);
+
+fn usable_request_id(value: &HeaderValue) -> Option<HeaderValue> {
+    let id = value.as_bytes();
+    let ok =
+        !id.is_empty() && id.len() <= MAX_REQUEST_ID_LEN && id.iter().all(u8::is_ascii_graphic);
+    ok.then(|| value.clone())
+}
+
+/// A random id for a request that brought none. A hyphenated UUID is always
+/// a valid header value; the numeric fallback only keeps this panic-free.
+fn generated_request_id() -> HeaderValue {
+    let uuid = uuid::Uuid::new_v4();
+    HeaderValue::try_from(uuid.hyphenated().to_string())
+        .unwrap_or_else(|_| HeaderValue::from(uuid.as_u64_pair().0))
+}
+
+/// Keeps the client's `X-Request-Id`, or generates one, and echoes it in the
+/// response. Error bodies pick it up from the request header.
+pub async fn request_id(mut req: Request, next: Next) -> Response {
+    let value = req
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(usable_request_id)
+        .unwrap_or_else(generated_request_id);
+    // both kinds are visible ASCII, so `to_str` cannot fail
+    let id = value.to_str().unwrap_or_default().to_string();
+
+    req.headers_mut().insert(REQUEST_ID_HEADER, value.clone());
+    req.extensions_mut().insert(RequestId(id));
+
+    let mut resp = next.run(req).await;
+    resp.headers_mut().insert(REQUEST_ID_HEADER, value);
+    resp
+}
+
+/// Runs each request in a span with its method, route and id. When the
+/// response is ready its status and latency are recorded on the span and
+/// logged.
+pub async fn trace_requests(req: Request, next: Next) -> Response {
+    let route = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or("<unmatched>", |path| path.as_str())
+        .to_string();
+    let request_id = req
+        .extensions()
+        .get::<RequestId>()
+        .map(|id| id.0.clone())
+        .unwrap_or_default();
+    let span = tracing::info_span!(
+        "request",
+        method = %req.method(),
+        route,
+        request_id,
+        status = tracing::field::Empty,
+        latency_ms = tracing::field::Empty,
+    );
+
+    async move {
+        let started = Instant::now();
+        let resp = next.run(req).await;
+        let status = resp.status().as_u16();
+        let latency_ms = started.elapsed().as_secs_f64() * 1000.0;
+        let span = tracing::Span::current();
+        span.record("status", status);
+        span.record("latency_ms", latency_ms);
+
+        if resp.status().is_server_error() {
+            tracing::error!(status, latency_ms, "request failed");
+        } else {
+            tracing::info!(status, latency_ms, "request finished");
+        }
+        resp
+    }
+    .instrument(span)
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LogFormat, generated_request_id, usable_request_id};
+    use axum::http::HeaderValue;
+
+    #[test]
+    fn request_ids_must_be_short_visible_ascii() {
+        let id = HeaderValue::from_static("abc-123");
+        assert_eq!(Some(id.clone()), usable_request_id(&id));
+        assert_eq!(None, usable_request_id(&HeaderValue::from_static("")));
+        assert_eq!(None, usable_request_id(&HeaderValue::from_static("a b")));
+        let long = "x".repeat(129);
+        assert_eq!(
+            None,
+            usable_request_id(&HeaderValue::from_str(&long).unwrap())
+        );
+        let latin1 = HeaderValue::from_bytes(b"caf\xe9").unwrap();
+        assert_eq!(None, usable_request_id(&latin1));
+    }
+
+    #[test]
+    fn generated_request_ids_are_usable() {
+        let id = generated_request_id();
+        assert_eq!(36, id.len());
+        assert_eq!(Some(id.clone()), usable_request_id(&id));
+    }
+
+    #[test]
+    fn log_format_parses() {
+        assert_eq!(LogFormat::Json, "json".parse().unwrap());
+        assert!("xml".parse::<LogFormat>().is_err());
+    }
+}
@@ -0,0 +1,167 @@
+mod test_server;
+
+use flate2::read::GzDecoder;
+use serde_json::{Value, json};
+use std::io::Read;
+use tempfile::tempdir;
+use test_server::{app_state, spawn_app};
+use week01_ownership_store::api::state::AppState;
+use week01_ownership_store::app::{self, AppConfig, Compression};
+
+async fn state(dir: &std::path::Path, keys: usize) -> AppState {
+    let state = app_state(&dir.join("data.json"));
+    let mut map = state.store.write().await;
+    for i in 0..keys {
+        map.insert(format!("key-{i}"), json!({ "n": i, "note": "some value" }));
+    }
+    drop(map);
+    state
+}
+
+async fn get(base: &str, path: &str, accept_encoding: &str) -> reqwest::Response {
+    reqwest::Client::new()
+        .get(format!("{base}{path}"))
+        .header("accept-encoding", accept_encoding)
+        .send()
+        .await
+        .unwrap()
+}
+
+fn encoding(resp: &reqwest::Response) -> Option<&str> {
+    resp.headers()
+        .get("content-encoding")
+        .map(|v| v.to_str().unwrap())
+}
+
+#[tokio::test]
+async fn large_responses_use_the_negotiated_encoding() {
+    let dir = tempdir().unwrap();
+    let app = app::router(state(dir.path(), 200).await, &AppConfig::default());
+    let (addr, _handle) = spawn_app(app).await;
+    let base = format!("http://{addr}");
+
+    let plain = get(&base, "/kv", "identity").await;
+    assert_eq!(None, encoding(&plain));
+    let plain = plain.bytes().await.unwrap();
+
+    let resp = get(&base, "/kv", "gzip").await;
+    assert_eq!(Some("gzip"), encoding(&resp));
+    assert_eq!("accept-encoding", resp.headers()["vary"]);
+    let compressed = resp.bytes().await.unwrap();
+    assert!(compressed.len() < plain.len() / 2);
+    let mut body = Vec::new();
+    GzDecoder::new(&compressed[..])
+        .read_to_end(&mut body)
+        .unwrap();
+    assert_eq!(plain, body);
+
+    let resp = get(&base, "/kv", "zstd").await;
+    assert_eq!(Some("zstd"), encoding(&resp));
+    let body = zstd::decode_all(&resp.bytes().await.unwrap()[..]).unwrap();
+    assert_eq!(plain, body);
+
+    let resp = get(&base, "/kv", "gzip;q=0.5, br").await;
+    assert_eq!(Some("br"), encoding(&resp));
+}
+
+#[tokio::test]
+async fn small_responses_and_streams_are_not_compressed() {
+    let dir = tempdir().unwrap();
+    let app = app::router(state(dir.path(), 0).await, &AppConfig::default());
+    let (addr, _handle) = spawn_app(app).await;
+    let base = format!("http://{addr}");
+
+    let resp = get(&base, "/healthz", "gzip").await;
+    assert_eq!(200, resp.status().as_u16());
+    assert_eq!(None, encoding(&resp));
+
+    let resp = reqwest::Client::new()
+        .post(format!("{base}/v1/grep"))
+        .header("accept", "application/x-ndjson")
+        .header("accept-encoding", "gzip")
+        .json(&json!({ "text": "match\n".repeat(1000), "pattern": "match" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(200, resp.status().as_u16());
+    assert_eq!(None, encoding(&resp));
+}
+
+#[tokio::test]
+async fn compression_settings_apply() {
+    let dir = tempdir().unwrap();
+    let state = state(dir.path(), 200).await;
+
+    let off = AppConfig {
+        compression: Compression {
+            enabled: false,
+            ..Compression::default()
+        },
+        ..AppConfig::default()
+    };
+    let (addr, _handle) = spawn_app(app::router(state.clone(), &off)).await;
+    let resp = get(&format!("http://{addr}"), "/kv", "gzip").await;
+    assert_eq!(None, encoding(&resp));
+
+    let low = AppConfig {
+        compression: Compression {
+            enabled: true,
+            min_size: 1,
+        },
+        ..AppConfig::default()
+    };
+    let (addr, _handle) = spawn_app(app::router(state, &low)).await;
+    let resp = get(&format!("http://{addr}"), "/healthz", "gzip").await;
+    assert_eq!(Some("gzip"), encoding(&resp));
+}
+
+#[tokio::test]
+async fn kv_accepts_compressed_bodies_within_the_limit() {
+    let dir = tempdir().unwrap();
+    let config = AppConfig {
+        max_body: Some(1024),
+        ..AppConfig::default()
+    };
+    let app = app::router(state(dir.path(), 0).await, &config);
+    let (addr, _handle) = spawn_app(app).await;
+    let client = reqwest::Client::new();
+    let post = |body: Value| {
+        let compressed = zstd::encode_all(body.to_string().as_bytes(), 19).unwrap();
+        client
+            .post(format!("http://{addr}/kv/k"))
+            .header("content-type", "application/json")
+            .header("content-encoding", "zstd")
+            .header("x-request-id", "bomb-1")
+            .body(compressed)
+            .send()
+    };
+
+    let resp = post(json!({ "value": "hello" })).await.unwrap();
+    assert_eq!(200, resp.status().as_u16());
+
+    // tiny on the wire, far over the limit once inflated
+    let bomb = json!({ "value": "a".repeat(1_000_000) });
+    assert!(
+        zstd::encode_all(bomb.to_string().as_bytes(), 19)
+            .unwrap()
+            .len()
+            < 1024
+    );
+    let resp = post(bomb).await.unwrap();
+    assert_eq!(413, resp.status().as_u16());
+    assert_eq!(
+        json!({ "error": {
+            "code": "PAYLOAD_TOO_LARGE",
+            "message": "request body exceeds the size limit",
+            "request_id": "bomb-1",
+        }}),
+        resp.json::<Value>().await.unwrap()
+    );
+
+    let resp = client
+        .get(format!("http://{addr}/kv/k"))
+        .send()
+        .await
+        .unwrap();
+    assert!(resp.text().await.unwrap().contains("hello"));
+}
@@ -0,0 +1,226 @@
+mod test_server;
+
+use serde_json::{Value, json};
+use tempfile::tempdir;
+use test_server::{app_state as state, spawn_app};
+use week01_ownership_store::app::{self, AppConfig, PathPrefix, validate_prefix};
+
+#[tokio::test]
+async fn one_server_serves_text_kv_and_docs() {
+    let dir = tempdir().unwrap();
+    let app = app::router(state(&dir.path().join("data.json")), &AppConfig::default());
+    let (addr, _handle) = spawn_app(app).await;
+    let base = format!("http://{addr}");
+    let client = reqwest::Client::new();
+
+    let resp = client
+        .post(format!("{base}/v1/stats"))
+        .json(&json!({"text": "a b\nc\n"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["words"], 3);
+
+    let resp = client
+        .post(format!("{base}/kv/greeting"))
+        .json(&json!({"value": "hello"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+
+    let resp = client
+        .get(format!("{base}/kv/greeting"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    assert!(resp.text().await.unwrap().contains("hello"));
+
+    let resp = client
+        .get(format!("{base}/kv/missing"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 404);
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["error"]["code"], "NOT_FOUND");
+
+    let resp = client
+        .get(format!("{base}/openapi.json"))
+        .send()
+        .await
+        .unwrap();
+    let doc: Value = resp.json().await.unwrap();
+    assert!(doc["paths"]["/v1/grep"].is_object());
+    assert!(doc["paths"]["/kv/{key}"].is_object());
+}
+
+#[tokio::test]
+async fn prefixes_move_routes_and_documented_paths() {
+    let dir = tempdir().unwrap();
+    let config = AppConfig {
+        text_prefix: Some("/text".parse().unwrap()),
+        kv_prefix: Some("/store".parse().unwrap()),
+        ..AppConfig::default()
+    };
+    let app = app::router(state(&dir.path().join("data.json")), &config);
+    let (addr, _handle) = spawn_app(app).await;
+    let base = format!("http://{addr}");
+    let client = reqwest::Client::new();
+
+    let resp = client
+        .post(format!("{base}/text/v1/uniq"))
+        .json(&json!({"text": "a\na\n"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+
+    let resp = client
+        .post(format!("{base}/v1/uniq"))
+        .json(&json!({"text": "a\na\n"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 404);
+
+    let resp = client.get(format!("{base}/store/kv")).send().await.unwrap();
+    assert_eq!(resp.status(), 200);
+
+    let doc: Value = client
+        .get(format!("{base}/openapi.json"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert!(doc["paths"]["/text/v1/stats"].is_object());
+    assert!(doc["paths"]["/store/kv/{key}"].is_object());
+    assert!(doc["paths"]["/v1/stats"].is_null());
+}
+
+#[test]
+fn prefixes_are_validated() {
+    assert!(validate_prefix("/api").is_ok());
+    assert!(validate_prefix("/api/text").is_ok());
+    assert!(validate_prefix("api").is_err());
+    assert!(validate_prefix("/api/").is_err());
+    assert!(validate_prefix("/").is_err());
+    assert!(validate_prefix("/{id}").is_err());
+
+    assert_eq!("/api", "/api".parse::<PathPrefix>().unwrap().as_str());
+    assert!("/api/".parse::<PathPrefix>().is_err());
+}
+
+#[tokio::test]
+async fn read_only_rejects_kv_writes_only() {
+    let dir = tempdir().unwrap();
+    let config = AppConfig {
+        read_only: true,
+        ..AppConfig::default()
+    };
+    let app = app::router(state(&dir.path().join("data.json")), &config);
+    let (addr, _handle) = spawn_app(app).await;
+    let base = format!("http://{addr}");
+    let client = reqwest::Client::new();
+
+    let resp = client
+        .post(format!("{base}/kv/k"))
+        .json(&json!(1))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 403);
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["error"]["code"], "READ_ONLY");
+
+    let resp = client.delete(format!("{base}/kv/k")).send().await.unwrap();
+    assert_eq!(resp.status(), 403);
+    assert!(!dir.path().join("data.json").exists());
+
+    let resp = client.get(format!("{base}/kv")).send().await.unwrap();
+    assert_eq!(resp.status(), 200);
+
+    let resp = client
+        .post(format!("{base}/v1/stats"))
+        .json(&json!({"text": "a\n"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+}
+
+#[tokio::test]
+async fn max_body_applies_to_every_route() {
+    let dir = tempdir().unwrap();
+    let config = AppConfig {
+        max_body: Some(64),
+        ..AppConfig::default()
+    };
+    let app = app::router(state(&dir.path().join("data.json")), &config);
+    let (addr, _handle) = spawn_app(app).await;
+    let base = format!("http://{addr}");
+    let client = reqwest::Client::new();
+    let big = "x".repeat(100);
+
+    for (path, body) in [("/v1/stats", json!({ "text": big })), ("/kv/k", json!(big))] {
+        let resp = client
+            .post(format!("{base}{path}"))
+            .header("x-request-id", "big-1")
+            .json(&body)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), 413, "{path}");
+        assert_eq!(
+            resp.json::<Value>().await.unwrap(),
+            json!({ "error": {
+                "code": "PAYLOAD_TOO_LARGE",
+                "message": "request body exceeds the size limit",
+                "request_id": "big-1",
+            }}),
+            "{path}"
+        );
+    }
+}
+
+#[tokio::test]
+async fn request_ids_are_echoed_or_generated() {
+    let dir = tempdir().unwrap();
+    let app = app::router(state(&dir.path().join("data.json")), &AppConfig::default());
+    let (addr, _handle) = spawn_app(app).await;
+    let base = format!("http://{addr}");
+    let client = reqwest::Client::new();
+
+    let resp = client
+        .get(format!("{base}/kv/missing"))
+        .header("x-request-id", "client-7")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.headers()["x-request-id"], "client-7");
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["error"]["request_id"], "client-7");
+
+    let resp = client
+        .post(format!("{base}/v1/grep"))
+        .json(&json!({"text": "a\n", "pattern": ""}))
+        .send()
+        .await
+        .unwrap();
+    let generated = resp.headers()["x-request-id"].to_str().unwrap().to_string();
+    assert_eq!(36, generated.len());
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["error"]["request_id"], generated.as_str());
+
+    let resp = client
+        .get(format!("{base}/openapi.json"))
+        .send()
+        .await
+        .unwrap();
+    assert!(resp.headers().contains_key("x-request-id"));
+}
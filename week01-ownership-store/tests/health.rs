@@ -0,0 +1,149 @@
+mod test_server;
+
+use serde_json::{Value, json};
+use tempfile::tempdir;
+use test_server::{app_state as state, spawn_app};
+use week01_ownership_store::app::{self, AppConfig};
+
+async fn readyz(client: &reqwest::Client, base: &str) -> (u16, Value) {
+    let resp = client.get(format!("{base}/readyz")).send().await.unwrap();
+    (resp.status().as_u16(), resp.json().await.unwrap())
+}
+
+fn check<'a>(report: &'a Value, name: &str) -> &'a Value {
+    report["checks"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|c| c["name"] == name)
+        .unwrap()
+}
+
+#[tokio::test]
+async fn healthz_is_always_ok() {
+    let dir = tempdir().unwrap();
+    let config = AppConfig {
+        load_error: Some("broken".to_string()),
+        ..AppConfig::default()
+    };
+    let (addr, _handle) = spawn_app(app::router(state(&dir.path().join("d.json")), &config)).await;
+
+    let resp = reqwest::get(format!("http://{addr}/healthz"))
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    assert_eq!(resp.json::<Value>().await.unwrap(), json!({"status": "ok"}));
+}
+
+#[tokio::test]
+async fn readiness_follows_writability_and_saves() {
+    let dir = tempdir().unwrap();
+    let data_file = dir.path().join("data.json");
+    let (addr, _handle) = spawn_app(app::router(state(&data_file), &AppConfig::default())).await;
+    let base = format!("http://{addr}");
+    let client = reqwest::Client::new();
+
+    let (status, report) = readyz(&client, &base).await;
+    assert_eq!(200, status, "{report}");
+    assert_eq!("ready", report["status"]);
+    assert!(!dir.path().join("data.readyz").exists());
+
+    // saves fail once the directory is gone
+    let moved = dir.path().with_extension("moved");
+    std::fs::rename(dir.path(), &moved).unwrap();
+    let resp = client
+        .post(format!("{base}/kv/k"))
+        .json(&json!(1))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 500);
+
+    let (status, report) = readyz(&client, &base).await;
+    assert_eq!(503, status);
+    assert_eq!("not_ready", report["status"]);
+    assert_eq!(false, check(&report, "data_dir_writable")["ok"]);
+    assert_eq!(false, check(&report, "last_save")["ok"]);
+
+    std::fs::rename(&moved, dir.path()).unwrap();
+    let resp = client
+        .post(format!("{base}/kv/k"))
+        .json(&json!(2))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+
+    let (status, report) = readyz(&client, &base).await;
+    assert_eq!(200, status, "{report}");
+}
+
+#[tokio::test]
+async fn writability_probe_is_reused_between_probes() {
+    let dir = tempdir().unwrap();
+    let data_file = dir.path().join("data.json");
+    let (addr, _handle) = spawn_app(app::router(state(&data_file), &AppConfig::default())).await;
+    let base = format!("http://{addr}");
+    let client = reqwest::Client::new();
+
+    let (status, _) = readyz(&client, &base).await;
+    assert_eq!(200, status);
+
+    // a new probe would fail, but the last one is still fresh
+    let moved = dir.path().with_extension("moved");
+    std::fs::rename(dir.path(), &moved).unwrap();
+    let (status, report) = readyz(&client, &base).await;
+    std::fs::rename(&moved, dir.path()).unwrap();
+
+    assert_eq!(200, status, "{report}");
+    assert_eq!(true, check(&report, "data_dir_writable")["ok"]);
+}
+
+#[tokio::test]
+async fn load_error_is_not_ready() {
+    let dir = tempdir().unwrap();
+    let config = AppConfig {
+        load_error: Some("expected value at line 1 column 2".to_string()),
+        read_only: true,
+        ..AppConfig::default()
+    };
+    let (addr, _handle) = spawn_app(app::router(state(&dir.path().join("d.json")), &config)).await;
+    let client = reqwest::Client::new();
+
+    let (status, report) = readyz(&client, &format!("http://{addr}")).await;
+
+    assert_eq!(503, status);
+    let data_file = check(&report, "data_file");
+    assert_eq!(false, data_file["ok"]);
+    assert!(
+        data_file["detail"]
+            .as_str()
+            .unwrap()
+            .contains("expected value")
+    );
+    assert_eq!(true, check(&report, "data_dir_writable")["ok"]);
+}
+
+#[tokio::test]
+async fn a_failed_save_only_affects_its_own_app() {
+    let dir = tempdir().unwrap();
+    let broken = state(&dir.path().join("missing").join("data.json"));
+    let healthy = state(&dir.path().join("data.json"));
+    let (broken, _h1) = spawn_app(app::router(broken, &AppConfig::default())).await;
+    let (healthy, _h2) = spawn_app(app::router(healthy, &AppConfig::default())).await;
+    let client = reqwest::Client::new();
+
+    let resp = client
+        .post(format!("http://{broken}/kv/k"))
+        .json(&json!(1))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 500);
+    let (_, report) = readyz(&client, &format!("http://{broken}")).await;
+    assert_eq!(false, check(&report, "last_save")["ok"]);
+
+    let (status, report) = readyz(&client, &format!("http://{healthy}")).await;
+    assert_eq!(200, status, "{report}");
+    assert_eq!("no save yet", check(&report, "last_save")["detail"]);
+}
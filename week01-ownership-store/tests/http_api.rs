@@ -1,23 +1,20 @@
+mod test_server;
+
 use axum::{
     body::Body,
     http::{Request, StatusCode},
 };
 use serde_json::json;
 use tempfile::tempdir;
+use test_server::app_state;
 use tower::ServiceExt;
-use week01_ownership_store::api::state::AppState;
 
 #[tokio::test]
 async fn get_missing_key_returns_404() {
     let dir = tempdir().unwrap();
     let data_file = dir.path().join("data.json");
 
-    let store = week01_ownership_store::storage::new_store();
-    let state = AppState {
-        store,
-        data_file: data_file.to_string_lossy().to_string(),
-    };
-    let app = week01_ownership_store::api::router(state);
+    let app = week01_ownership_store::api::router(app_state(&data_file));
 
     let req = Request::builder()
         .method("GET")
@@ -34,12 +31,7 @@ async fn put_then_get_returns_value() {
     let dir = tempdir().unwrap();
     let data_file = dir.path().join("data.json");
 
-    let store = week01_ownership_store::storage::new_store();
-    let state = AppState {
-        store,
-        data_file: data_file.to_string_lossy().to_string(),
-    };
-    let app = week01_ownership_store::api::router(state);
+    let app = week01_ownership_store::api::router(app_state(&data_file));
 
     let body = json!({"name": "alice"}).to_string();
     let req = Request::builder()
@@ -67,12 +59,7 @@ async fn restart_preserves_data() {
     let data_file = dir.path().join("data.json");
     let data_path = data_file.to_string_lossy().to_string();
 
-    let store1 = week01_ownership_store::storage::new_store();
-    let state1 = AppState {
-        store: store1,
-        data_file: data_path.clone(),
-    };
-    let app1 = week01_ownership_store::api::router(state1);
+    let app1 = week01_ownership_store::api::router(app_state(&data_file));
 
     let body = json!({"x": 1}).to_string();
     let req = Request::builder()
@@ -83,18 +70,14 @@ async fn restart_preserves_data() {
         .unwrap();
     let _ = app1.oneshot(req).await.unwrap();
 
-    let store2 = week01_ownership_store::storage::new_store();
+    let state2 = app_state(&data_file);
     let map = week01_ownership_store::storage::file::load_from_file(&data_path)
         .await
         .unwrap();
     {
-        let mut guard = store2.write().await;
+        let mut guard = state2.store.write().await;
         *guard = map;
     }
-    let state2 = AppState {
-        store: store2,
-        data_file: data_path.clone(),
-    };
     let app2 = week01_ownership_store::api::router(state2);
 
     let get_req = Request::builder()
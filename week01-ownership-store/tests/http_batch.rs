@@ -0,0 +1,94 @@
+use axum::{
+    body::{Body, to_bytes},
+    http::{Request, StatusCode},
+};
+use serde_json::{Value, json};
+use tower::ServiceExt;
+use week01_ownership_store::http::{Limits, router, router_with_limits};
+
+fn batch(body: Value) -> Request<Body> {
+    Request::builder()
+        .method("POST")
+        .uri("/v1/batch")
+        .header("content-type", "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap()
+}
+
+#[tokio::test]
+async fn batch_runs_each_op_and_keeps_order() {
+    let resp = router()
+        .oneshot(batch(json!([
+            { "op": "stats", "text": "one two\nthree\n" },
+            { "op": "uniq", "text": "a\na\nb\n" },
+            { "op": "grep", "text": "foo\nbar\nfood\n", "pattern": "foo", "line_number": true },
+        ])))
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let bytes = to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+    let data: Value = serde_json::from_slice(&bytes).unwrap();
+    let results = data["results"].as_array().unwrap();
+
+    assert_eq!(results.len(), 3);
+    assert_eq!(results[0]["words"], 3);
+    assert_eq!(results[1]["text"], "a\nb\n");
+    assert_eq!(results[2]["count"], 2);
+    assert_eq!(results[2]["matches"][1]["line"], 3);
+}
+
+#[tokio::test]
+async fn failing_items_do_not_fail_the_batch() {
+    let resp = router()
+        .oneshot(batch(json!([
+            { "op": "grep", "text": "foo\n", "pattern": "" },
+            { "op": "sort", "text": "b\na\n" },
+            { "op": "stats", "text": "ok\n" },
+        ])))
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let bytes = to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+    let data: Value = serde_json::from_slice(&bytes).unwrap();
+    let results = data["results"].as_array().unwrap();
+
+    assert_eq!(results[0]["error"]["code"], "VALIDATION_ERROR");
+    assert_eq!(results[1]["error"]["code"], "INVALID_BODY");
+    assert_eq!(results[2]["lines"], 1);
+}
+
+#[tokio::test]
+async fn batch_item_count_and_item_size_are_limited() {
+    let app = router_with_limits(Limits {
+        max_batch_items: 2,
+        stats_body_bytes: 4,
+        batch_workers: 1,
+        ..Limits::default()
+    });
+
+    let resp = app
+        .clone()
+        .oneshot(batch(json!([
+            { "op": "stats", "text": "a" },
+            { "op": "stats", "text": "b" },
+            { "op": "stats", "text": "c" },
+        ])))
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+    let resp = app
+        .oneshot(batch(json!([
+            { "op": "stats", "text": "small" },
+            { "op": "stats", "text": "a" },
+        ])))
+        .await
+        .unwrap();
+    let bytes = to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+    let data: Value = serde_json::from_slice(&bytes).unwrap();
+
+    assert_eq!(data["results"][0]["error"]["code"], "PAYLOAD_TOO_LARGE");
+    assert_eq!(data["results"][1]["lines"], 1);
+}
@@ -0,0 +1,218 @@
+mod test_server;
+
+use axum::{
+    Router,
+    body::{Body, to_bytes},
+    extract::DefaultBodyLimit,
+    http::{Request, StatusCode},
+    response::Response,
+};
+use serde_json::{Value, json};
+use tempfile::{TempDir, tempdir};
+use test_server::app_state;
+use tower::ServiceExt;
+use week01_ownership_store::api;
+use week01_ownership_store::error::{ErrorFormat, with_format};
+use week01_ownership_store::http::{self, Limits};
+
+const KV_BODY_LIMIT: usize = 64;
+
+fn test_app(format: ErrorFormat) -> (Router, TempDir) {
+    let dir = tempdir().unwrap();
+    let state = app_state(&dir.path().join("data.json"));
+    let limits = Limits {
+        max_lines: 1,
+        ..Limits::default()
+    };
+    let kv = api::router(state).layer(DefaultBodyLimit::max(KV_BODY_LIMIT));
+    let router = http::router_with_limits(limits).merge(kv);
+
+    (with_format(router, format), dir)
+}
+
+fn missing_key() -> Request<Body> {
+    Request::builder()
+        .uri("/kv/missing")
+        .header("x-request-id", "req-42")
+        .body(Body::empty())
+        .unwrap()
+}
+
+fn too_many_lines() -> Request<Body> {
+    Request::builder()
+        .method("POST")
+        .uri("/v1/stats")
+        .header("content-type", "application/json")
+        .body(Body::from(json!({ "text": "a\nb\n" }).to_string()))
+        .unwrap()
+}
+
+/// `POST /kv/k` requests the JSON extractor rejects (malformed, not JSON,
+/// over the body limit), with the status and error each should get.
+fn bad_kv_puts() -> Vec<(Request<Body>, StatusCode, &'static str, &'static str)> {
+    let put = |content_type: &str, body: String| {
+        Request::builder()
+            .method("POST")
+            .uri("/kv/k")
+            .header("content-type", content_type)
+            .header("x-request-id", "req-7")
+            .body(Body::from(body))
+            .unwrap()
+    };
+    vec![
+        (
+            put("application/json", "{\"a\":".to_string()),
+            StatusCode::BAD_REQUEST,
+            "INVALID_BODY",
+            "Failed to parse the request body as JSON: a: EOF while parsing a value at line 1 column 5",
+        ),
+        (
+            put("text/plain", "1".to_string()),
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            "INVALID_BODY",
+            "Expected request with `Content-Type: application/json`",
+        ),
+        (
+            put(
+                "application/json",
+                json!("a".repeat(KV_BODY_LIMIT)).to_string(),
+            ),
+            StatusCode::PAYLOAD_TOO_LARGE,
+            "PAYLOAD_TOO_LARGE",
+            "request body exceeds the size limit",
+        ),
+    ]
+}
+
+async fn body(resp: Response) -> Value {
+    let bytes = to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+    serde_json::from_slice(&bytes).unwrap()
+}
+
+#[tokio::test]
+async fn both_routers_use_the_same_envelope() {
+    let (app, _dir) = test_app(ErrorFormat::Envelope);
+
+    let resp = app.clone().oneshot(missing_key()).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    assert_eq!(
+        body(resp).await,
+        json!({ "error": {
+            "code": "NOT_FOUND",
+            "message": "key does not exist",
+            "request_id": "req-42",
+        }})
+    );
+
+    let resp = app.oneshot(too_many_lines()).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    assert_eq!(
+        body(resp).await,
+        json!({ "error": {
+            "code": "PAYLOAD_TOO_LARGE",
+            "message": "line count exceeds 1",
+            "details": { "limit": 1 },
+        }})
+    );
+}
+
+#[tokio::test]
+async fn problem_json_when_configured_or_requested() {
+    let (app, _dir) = test_app(ErrorFormat::Problem);
+    let resp = app.oneshot(missing_key()).await.unwrap();
+
+    assert_eq!(
+        resp.headers().get("content-type").unwrap(),
+        "application/problem+json"
+    );
+    assert_eq!(
+        body(resp).await,
+        json!({
+            "type": "about:blank",
+            "title": "Not Found",
+            "status": 404,
+            "detail": "key does not exist",
+            "code": "NOT_FOUND",
+            "request_id": "req-42",
+        })
+    );
+
+    let (app, _dir) = test_app(ErrorFormat::Envelope);
+    let mut req = too_many_lines();
+    req.headers_mut()
+        .insert("accept", "application/problem+json".parse().unwrap());
+    let resp = app.oneshot(req).await.unwrap();
+
+    assert_eq!(
+        resp.headers().get("content-type").unwrap(),
+        "application/problem+json"
+    );
+    assert_eq!(body(resp).await["details"]["limit"], 1);
+}
+
+#[tokio::test]
+async fn legacy_format_keeps_the_old_shapes() {
+    let (app, _dir) = test_app(ErrorFormat::Legacy);
+
+    let resp = app.clone().oneshot(missing_key()).await.unwrap();
+    assert_eq!(
+        body(resp).await,
+        json!({ "error": "not_found", "message": "key does not exist" })
+    );
+
+    let resp = app.oneshot(too_many_lines()).await.unwrap();
+    assert_eq!(
+        body(resp).await,
+        json!({ "error": { "code": "PAYLOAD_TOO_LARGE", "message": "line count exceeds 1" } })
+    );
+}
+
+#[tokio::test]
+async fn kv_body_rejections_use_the_configured_format() {
+    let (app, _dir) = test_app(ErrorFormat::Envelope);
+    for (req, status, code, message) in bad_kv_puts() {
+        let resp = app.clone().oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), status);
+        assert_eq!(
+            body(resp).await,
+            json!({ "error": { "code": code, "message": message, "request_id": "req-7" } })
+        );
+    }
+
+    let (app, _dir) = test_app(ErrorFormat::Problem);
+    for (req, status, code, message) in bad_kv_puts() {
+        let resp = app.clone().oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), status);
+        assert_eq!(
+            resp.headers().get("content-type").unwrap(),
+            "application/problem+json"
+        );
+        assert_eq!(
+            body(resp).await,
+            json!({
+                "type": "about:blank",
+                "title": status.canonical_reason().unwrap(),
+                "status": status.as_u16(),
+                "detail": message,
+                "code": code,
+                "request_id": "req-7",
+            })
+        );
+    }
+
+    let (app, _dir) = test_app(ErrorFormat::Legacy);
+    for (req, status, code, message) in bad_kv_puts() {
+        let resp = app.clone().oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), status);
+        assert_eq!(
+            body(resp).await,
+            json!({ "error": { "code": code, "message": message } })
+        );
+    }
+}
+
+#[test]
+fn error_format_parses_config_values() {
+    assert_eq!(Ok(ErrorFormat::Legacy), "legacy".parse());
+    assert!("xml".parse::<ErrorFormat>().is_err());
+}
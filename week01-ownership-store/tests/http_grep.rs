@@ -40,3 +40,101 @@ async fn grep_with_line_numbers() {
     let resp = app.oneshot(req).await.unwrap();
     assert!(resp.status().is_success());
 }
+
+#[tokio::test]
+async fn grep_streams_ndjson_with_trailing_summary() {
+    let app = week01_ownership_store::http::router();
+    let body = json!({
+        "text": "foo\nbar\nfood\n",
+        "pattern": "foo",
+        "line_number": true
+    })
+    .to_string();
+
+    let req = Request::builder()
+        .method("POST")
+        .uri("/v1/grep")
+        .header("content-type", "application/json")
+        .header("accept", "application/x-ndjson")
+        .body(Body::from(body))
+        .unwrap();
+
+    let resp = app.oneshot(req).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(
+        resp.headers().get("content-type").unwrap(),
+        "application/x-ndjson"
+    );
+
+    let bytes = axum::body::to_bytes(resp.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let lines: Vec<serde_json::Value> = std::str::from_utf8(&bytes)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+
+    assert_eq!(
+        lines,
+        vec![
+            json!({ "line": 1, "text": "foo" }),
+            json!({ "line": 3, "text": "food" }),
+            json!({ "count": 2 }),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn grep_ndjson_validation_errors_stay_json() {
+    let app = week01_ownership_store::http::router();
+    let body = json!({ "text": "foo\n", "pattern": "" }).to_string();
+
+    let req = Request::builder()
+        .method("POST")
+        .uri("/v1/grep")
+        .header("content-type", "application/json")
+        .header("accept", "application/json, application/x-ndjson;q=0.9")
+        .body(Body::from(body))
+        .unwrap();
+
+    let resp = app.oneshot(req).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        resp.headers().get("content-type").unwrap(),
+        "application/json"
+    );
+}
+
+async fn grep_content_type(accept: &str) -> String {
+    let app = week01_ownership_store::http::router();
+    let body = json!({ "text": "foo\n", "pattern": "foo" }).to_string();
+    let req = Request::builder()
+        .method("POST")
+        .uri("/v1/grep")
+        .header("content-type", "application/json")
+        .header("accept", accept)
+        .body(Body::from(body))
+        .unwrap();
+
+    let resp = app.oneshot(req).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    resp.headers()["content-type"].to_str().unwrap().to_string()
+}
+
+#[tokio::test]
+async fn grep_honors_accept_q_values() {
+    assert_eq!(
+        grep_content_type("application/json, application/x-ndjson;q=0.9").await,
+        "application/json"
+    );
+    assert_eq!(
+        grep_content_type("application/x-ndjson;q=0").await,
+        "application/json"
+    );
+    assert_eq!(
+        grep_content_type("application/json;q=0.5, application/x-ndjson").await,
+        "application/x-ndjson"
+    );
+    assert_eq!(grep_content_type("*/*").await, "application/json");
+}
@@ -0,0 +1,116 @@
+use axum::{
+    Router,
+    body::{Body, to_bytes},
+    http::{Request, StatusCode},
+};
+use flate2::{Compression, write::GzEncoder};
+use serde_json::{Value, json};
+use std::io::Write;
+use tower::ServiceExt;
+use week01_ownership_store::http::{Limits, router_with_limits};
+
+fn small_limits() -> Router {
+    router_with_limits(Limits {
+        stats_body_bytes: 64,
+        uniq_body_bytes: 1024,
+        grep_body_bytes: 1024,
+        max_lines: 3,
+        max_pattern_bytes: 4,
+        ..Limits::default()
+    })
+}
+
+fn post(uri: &str, body: Value) -> Request<Body> {
+    Request::builder()
+        .method("POST")
+        .uri(uri)
+        .header("content-type", "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap()
+}
+
+async fn error_code(resp: axum::response::Response) -> String {
+    let bytes = to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+    let data: Value = serde_json::from_slice(&bytes).unwrap();
+    data["error"]["code"].as_str().unwrap().to_string()
+}
+
+#[tokio::test]
+async fn body_limit_is_per_endpoint() {
+    let text = "a".repeat(100);
+
+    let resp = small_limits()
+        .oneshot(post("/v1/stats", json!({ "text": text })))
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    assert_eq!(error_code(resp).await, "PAYLOAD_TOO_LARGE");
+
+    let resp = small_limits()
+        .oneshot(post("/v1/uniq", json!({ "text": text })))
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn body_limit_applies_to_decompressed_size() {
+    let mut enc = GzEncoder::new(Vec::new(), Compression::best());
+    enc.write_all(json!({ "text": "a".repeat(10_000) }).to_string().as_bytes())
+        .unwrap();
+    let compressed = enc.finish().unwrap();
+    assert!(compressed.len() < 1024);
+
+    let req = Request::builder()
+        .method("POST")
+        .uri("/v1/uniq")
+        .header("content-type", "application/json")
+        .header("content-encoding", "gzip")
+        .body(Body::from(compressed))
+        .unwrap();
+
+    let resp = small_limits().oneshot(req).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+}
+
+#[tokio::test]
+async fn too_many_lines_returns_413() {
+    let resp = small_limits()
+        .oneshot(post("/v1/uniq", json!({ "text": "a\nb\nc\nd\n" })))
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+    let resp = small_limits()
+        .oneshot(post("/v1/uniq", json!({ "text": "a\nb\nc\n" })))
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn long_pattern_returns_400() {
+    let resp = small_limits()
+        .oneshot(post(
+            "/v1/grep",
+            json!({ "text": "abcde\n", "pattern": "abcde" }),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(error_code(resp).await, "VALIDATION_ERROR");
+}
+
+#[tokio::test]
+async fn malformed_json_uses_error_envelope() {
+    let req = Request::builder()
+        .method("POST")
+        .uri("/v1/stats")
+        .header("content-type", "application/json")
+        .body(Body::from("{\"text\":"))
+        .unwrap();
+
+    let resp = small_limits().oneshot(req).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(error_code(resp).await, "INVALID_BODY");
+}
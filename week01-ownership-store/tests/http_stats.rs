@@ -1,8 +1,10 @@
 use axum::{
-    body::Body,
+    body::{Body, to_bytes},
     http::{Request, StatusCode},
 };
+use flate2::{Compression, write::GzEncoder};
 use serde_json::json;
+use std::io::Write;
 use tower::ServiceExt;
 
 #[tokio::test]
@@ -107,3 +109,74 @@ async fn endpoints_return_json_content_type() {
             .starts_with("application/json")
     );
 }
+
+#[tokio::test]
+async fn stats_accepts_gzip_request_body() {
+    let app = week01_ownership_store::http::router();
+    let mut enc = GzEncoder::new(Vec::new(), Compression::default());
+    enc.write_all(json!({ "text": "one two\nthree\n" }).to_string().as_bytes())
+        .unwrap();
+    let req = Request::builder()
+        .method("POST")
+        .uri("/v1/stats")
+        .header("content-type", "application/json")
+        .header("content-encoding", "gzip")
+        .body(Body::from(enc.finish().unwrap()))
+        .unwrap();
+
+    let resp = app.oneshot(req).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let bytes = to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+    let data: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(data["lines"], 2);
+    assert_eq!(data["words"], 3);
+}
+
+#[tokio::test]
+async fn stats_rejects_unsupported_content_encoding() {
+    let app = week01_ownership_store::http::router();
+    let req = Request::builder()
+        .method("POST")
+        .uri("/v1/stats")
+        .header("content-type", "application/json")
+        .header("content-encoding", "compress")
+        .body(Body::from(json!({ "text": "a" }).to_string()))
+        .unwrap();
+
+    let resp = app.oneshot(req).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+}
+
+#[tokio::test]
+async fn stats_percentiles_are_opt_in() {
+    let app = week01_ownership_store::http::router();
+    let plain = Request::builder()
+        .method("POST")
+        .uri("/v1/stats")
+        .header("content-type", "application/json")
+        .body(Body::from(json!({ "text": "a\nbbb\n" }).to_string()))
+        .unwrap();
+    let with_percentiles = Request::builder()
+        .method("POST")
+        .uri("/v1/stats")
+        .header("content-type", "application/json")
+        .body(Body::from(
+            json!({ "text": "a\nbbb\n", "percentiles": true }).to_string(),
+        ))
+        .unwrap();
+
+    let resp = app.clone().oneshot(plain).await.unwrap();
+    let bytes = to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+    let data: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+    assert!(data.get("line_lengths").is_none());
+
+    let resp = app.oneshot(with_percentiles).await.unwrap();
+    let bytes = to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+    let data: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(data["line_lengths"]["count"], 2);
+    assert_eq!(data["line_lengths"]["min"], 1.0);
+    assert_eq!(data["line_lengths"]["max"], 3.0);
+    assert_eq!(data["line_lengths"]["p50"], 1.0);
+    assert_eq!(data["line_lengths"]["p99"], 3.0);
+}
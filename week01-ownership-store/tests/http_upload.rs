@@ -0,0 +1,163 @@
+use axum::{
+    body::{Body, to_bytes},
+    http::{Request, StatusCode},
+    response::Response,
+};
+use serde_json::Value;
+use tower::ServiceExt;
+use week01_ownership_store::http::{Limits, router, router_with_limits};
+
+const BOUNDARY: &str = "textkit-boundary";
+
+fn plain(uri: &str, text: &str) -> Request<Body> {
+    Request::builder()
+        .method("POST")
+        .uri(uri)
+        .header("content-type", "text/plain; charset=utf-8")
+        .body(Body::from(text.to_string()))
+        .unwrap()
+}
+
+/// Builds a multipart body; a part named `file` is sent as a file upload.
+fn multipart(uri: &str, parts: &[(&str, &str)]) -> Request<Body> {
+    let mut body = String::new();
+    for (name, value) in parts {
+        body.push_str(&format!("--{BOUNDARY}\r\n"));
+        if *name == "file" {
+            body.push_str(
+                "Content-Disposition: form-data; name=\"file\"; filename=\"app.log\"\r\n\
+                 Content-Type: text/plain\r\n\r\n",
+            );
+        } else {
+            body.push_str(&format!(
+                "Content-Disposition: form-data; name=\"{name}\"\r\n\r\n"
+            ));
+        }
+        body.push_str(value);
+        body.push_str("\r\n");
+    }
+    body.push_str(&format!("--{BOUNDARY}--\r\n"));
+
+    Request::builder()
+        .method("POST")
+        .uri(uri)
+        .header(
+            "content-type",
+            format!("multipart/form-data; boundary={BOUNDARY}"),
+        )
+        .body(Body::from(body))
+        .unwrap()
+}
+
+async fn json(resp: Response) -> Value {
+    let bytes = to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+    serde_json::from_slice(&bytes).unwrap()
+}
+
+#[tokio::test]
+async fn stats_accepts_text_plain() {
+    let resp = router()
+        .oneshot(plain("/v1/stats?percentiles=true", "one two\nthree\n"))
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let data = json(resp).await;
+    assert_eq!(data["lines"], 2);
+    assert_eq!(data["words"], 3);
+    assert_eq!(data["line_lengths"]["max"], 7.0);
+}
+
+#[tokio::test]
+async fn grep_takes_options_from_query_string() {
+    let resp = router()
+        .oneshot(plain(
+            "/v1/grep?pattern=foo&line_number=true",
+            "foo\nbar\nfood\n",
+        ))
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let data = json(resp).await;
+    assert_eq!(data["count"], 2);
+    assert_eq!(data["matches"][1]["line"], 3);
+}
+
+#[tokio::test]
+async fn grep_text_plain_without_pattern_is_422() {
+    let resp = router().oneshot(plain("/v1/grep", "foo\n")).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(json(resp).await["error"]["code"], "INVALID_BODY");
+}
+
+#[tokio::test]
+async fn uniq_accepts_multipart_file_with_form_options() {
+    let resp = router()
+        .oneshot(multipart(
+            "/v1/uniq",
+            &[("all", "true"), ("file", "a\nb\na\n")],
+        ))
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let data = json(resp).await;
+    assert_eq!(data["text"], "a\nb\n");
+    assert_eq!(data["removed"], 1);
+}
+
+#[tokio::test]
+async fn multipart_without_file_part_is_400() {
+    let resp = router()
+        .oneshot(multipart("/v1/stats", &[("percentiles", "true")]))
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(json(resp).await["error"]["code"], "VALIDATION_ERROR");
+}
+
+#[tokio::test]
+async fn raw_and_multipart_bodies_respect_the_body_limit() {
+    let app = router_with_limits(Limits {
+        stats_body_bytes: 16,
+        ..Limits::default()
+    });
+    let text = "x".repeat(64);
+
+    let resp = app
+        .clone()
+        .oneshot(plain("/v1/stats", &text))
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+    let resp = app
+        .oneshot(multipart("/v1/stats", &[("file", &text)]))
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+}
+
+#[tokio::test]
+async fn json_and_multipart_ignore_the_query_string() {
+    let req = Request::builder()
+        .method("POST")
+        .uri("/v1/grep?pattern=nothing&line_number=maybe&%zz")
+        .header("content-type", "application/json")
+        .body(Body::from(r#"{"text":"foo\nbar\n","pattern":"bar"}"#))
+        .unwrap();
+    let resp = router().oneshot(req).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(json(resp).await["count"], 1);
+
+    let resp = router()
+        .oneshot(multipart(
+            "/v1/grep?line_number=maybe",
+            &[("file", "foo\nbar\n"), ("pattern", "foo")],
+        ))
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(json(resp).await["count"], 1);
+}
@@ -0,0 +1,160 @@
+mod test_server;
+
+use std::net::SocketAddr;
+use std::path::Path;
+use tempfile::tempdir;
+use test_server::app_state;
+use tokio::net::TcpListener;
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+use week01_ownership_store::app::{self, AppConfig};
+use week01_ownership_store::server::{self, Listener, ShutdownOptions, ShutdownSummary};
+use week01_ownership_store::tls::{TlsConfig, TlsError, TlsFiles, TlsListener};
+
+/// Writes a new self-signed certificate for `localhost` to `cert.pem` and
+/// `key.pem` in `dir` and returns the certificate.
+fn write_cert(dir: &Path) -> String {
+    let certified = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+    let cert = certified.cert.pem();
+    std::fs::write(dir.join("cert.pem"), &cert).unwrap();
+    std::fs::write(dir.join("key.pem"), certified.signing_key.serialize_pem()).unwrap();
+    cert
+}
+
+/// A client that trusts only `cert` and sends `localhost` to `addr`.
+fn client(cert: &str, addr: SocketAddr) -> reqwest::Client {
+    reqwest::Client::builder()
+        .tls_built_in_root_certs(false)
+        .add_root_certificate(reqwest::Certificate::from_pem(cert.as_bytes()).unwrap())
+        .resolve("localhost", addr)
+        .build()
+        .unwrap()
+}
+
+fn start(
+    dir: &Path,
+    listener: Listener,
+) -> (
+    oneshot::Sender<()>,
+    JoinHandle<std::io::Result<ShutdownSummary>>,
+) {
+    let state = app_state(&dir.join("data.json"));
+    let app = app::router(state.clone(), &AppConfig::default());
+    let (stop_tx, stop_rx) = oneshot::channel::<()>();
+    let server = tokio::spawn(server::serve(
+        [listener],
+        app,
+        state,
+        ShutdownOptions::default(),
+        async {
+            let _ = stop_rx.await;
+        },
+    ));
+    (stop_tx, server)
+}
+
+#[tokio::test]
+async fn https_serves_the_api_and_reloads_the_certificate() {
+    let dir = tempdir().unwrap();
+    let old_cert = write_cert(dir.path());
+    let tls = TlsConfig::load(TlsFiles {
+        cert: dir.path().join("cert.pem"),
+        key: dir.path().join("key.pem"),
+    })
+    .unwrap();
+    let tcp = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = tcp.local_addr().unwrap();
+    let url = format!("https://localhost:{}/healthz", addr.port());
+    let (stop, server) = start(
+        dir.path(),
+        Listener::Tls(TlsListener::new(tcp, tls.clone())),
+    );
+
+    let resp = client(&old_cert, addr).get(&url).send().await.unwrap();
+    assert_eq!(200, resp.status().as_u16());
+
+    let new_cert = write_cert(dir.path());
+    tls.reload().unwrap();
+
+    let resp = client(&new_cert, addr).get(&url).send().await.unwrap();
+    assert_eq!(200, resp.status().as_u16());
+    assert!(client(&old_cert, addr).get(&url).send().await.is_err());
+
+    // a broken file keeps the current certificate
+    std::fs::write(dir.path().join("key.pem"), "not a key").unwrap();
+    let err = tls.reload().unwrap_err();
+    assert!(matches!(err, TlsError::PrivateKey { .. }), "{err}");
+    assert!(err.to_string().contains("key.pem"), "{err}");
+    let resp = client(&new_cert, addr).get(&url).send().await.unwrap();
+    assert_eq!(200, resp.status().as_u16());
+
+    stop.send(()).unwrap();
+    assert!(server.await.unwrap().unwrap().drained);
+}
+
+#[tokio::test]
+async fn tls_config_reports_missing_files() {
+    let dir = tempdir().unwrap();
+
+    let err = TlsConfig::load(TlsFiles {
+        cert: dir.path().join("cert.pem"),
+        key: dir.path().join("key.pem"),
+    })
+    .unwrap_err();
+
+    assert!(matches!(err, TlsError::Certificate { .. }), "{err}");
+    assert!(
+        err.to_string().starts_with("cannot read certificate"),
+        "{err}"
+    );
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn unix_socket_serves_the_api_with_its_mode() {
+    use std::os::unix::fs::PermissionsExt;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use week01_ownership_store::server::UnixSocket;
+
+    let dir = tempdir().unwrap();
+    let socket = UnixSocket {
+        path: dir.path().join("store.sock"),
+        mode: 0o600,
+    };
+    let (stop, server) = start(dir.path(), Listener::Unix(socket.bind().unwrap()));
+
+    let mode = std::fs::metadata(&socket.path)
+        .unwrap()
+        .permissions()
+        .mode();
+    assert_eq!(0o600, mode & 0o777);
+    // the private directory the socket was bound in is gone
+    let entries: Vec<_> = std::fs::read_dir(dir.path())
+        .unwrap()
+        .map(|e| e.unwrap().file_name())
+        .collect();
+    assert_eq!(vec!["store.sock"], entries);
+
+    let mut conn = tokio::net::UnixStream::connect(&socket.path).await.unwrap();
+    conn.write_all(b"GET /healthz HTTP/1.1\r\nHost: store\r\nConnection: close\r\n\r\n")
+        .await
+        .unwrap();
+    let mut response = String::new();
+    conn.read_to_string(&mut response).await.unwrap();
+    assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+
+    let err = socket.bind().unwrap_err();
+    assert_eq!(std::io::ErrorKind::AddrInUse, err.kind());
+
+    stop.send(()).unwrap();
+    server.await.unwrap().unwrap();
+
+    // the socket file is left behind, but nobody answers on it any more
+    assert!(socket.bind().is_ok());
+
+    let file = UnixSocket {
+        path: dir.path().join("data.json"),
+        mode: 0o600,
+    };
+    assert!(file.bind().is_err());
+}
@@ -0,0 +1,103 @@
+mod test_server;
+
+use serde_json::json;
+use tempfile::tempdir;
+use test_server::{app_state, spawn_app};
+use week01_ownership_store::app::{self, AppConfig};
+
+#[tokio::test]
+async fn metrics_cover_both_apis_and_the_store() {
+    let dir = tempdir().unwrap();
+    let data_file = dir.path().join("data.json");
+    let state = app_state(&data_file);
+    let (addr, _handle) = spawn_app(app::router(state, &AppConfig::default())).await;
+    let base = format!("http://{addr}");
+    let client = reqwest::Client::new();
+
+    client
+        .post(format!("{base}/kv/a"))
+        .json(&json!("hello"))
+        .send()
+        .await
+        .unwrap();
+    client
+        .get(format!("{base}/kv/missing"))
+        .send()
+        .await
+        .unwrap();
+    client
+        .post(format!("{base}/v1/grep"))
+        .json(&json!({"text": "foo\nbar\n", "pattern": "foo"}))
+        .send()
+        .await
+        .unwrap();
+    client
+        .post(format!("{base}/v1/batch"))
+        .json(&json!([{"op": "stats", "text": "abc"}]))
+        .send()
+        .await
+        .unwrap();
+
+    let resp = client.get(format!("{base}/metrics")).send().await.unwrap();
+    assert_eq!(resp.status(), 200);
+    assert!(
+        resp.headers()["content-type"]
+            .to_str()
+            .unwrap()
+            .starts_with("text/plain; version=0.0.4")
+    );
+    let body = resp.text().await.unwrap();
+
+    for line in [
+        r#"http_requests_total{method="POST",route="/kv/{key}",status="200"} 1"#,
+        r#"http_requests_total{method="GET",route="/kv/{key}",status="404"} 1"#,
+        r#"http_requests_total{method="POST",route="/v1/grep",status="200"} 1"#,
+        r#"http_request_duration_seconds_count{method="POST",route="/v1/batch",status="200"} 1"#,
+        r#"text_operations_total{op="grep"} 1"#,
+        r#"text_operations_total{op="stats"} 1"#,
+        r#"text_input_bytes_total{op="grep"} 8"#,
+        "store_keys 1",
+        r#"store_value_bytes 7"#,
+        "store_saves_total 1",
+        "store_save_failures_total 0",
+    ] {
+        assert!(body.lines().any(|l| l == line), "missing {line}\n{body}");
+    }
+
+    let file_size = std::fs::metadata(&data_file).unwrap().len();
+    assert!(body.contains(&format!("store_data_file_bytes {file_size}\n")));
+    let last_save = body
+        .lines()
+        .find_map(|l| l.strip_prefix("store_last_save_timestamp_seconds "))
+        .unwrap();
+    assert!(last_save.parse::<f64>().unwrap() > 1.6e9);
+}
+
+#[tokio::test]
+async fn each_app_keeps_its_own_metrics() {
+    let dir = tempdir().unwrap();
+    let state = |name: &str| app_state(&dir.path().join(name));
+    let (first, _h1) = spawn_app(app::router(state("a.json"), &AppConfig::default())).await;
+    let (second, _h2) = spawn_app(app::router(state("b.json"), &AppConfig::default())).await;
+    let client = reqwest::Client::new();
+
+    client
+        .post(format!("http://{first}/kv/a"))
+        .json(&json!(1))
+        .send()
+        .await
+        .unwrap();
+
+    let scrape = |addr| {
+        let client = client.clone();
+        async move {
+            let resp = client.get(format!("http://{addr}/metrics")).send().await;
+            resp.unwrap().text().await.unwrap()
+        }
+    };
+    let body = scrape(first).await;
+    assert!(body.contains("store_saves_total 1\n"), "{body}");
+    let body = scrape(second).await;
+    assert!(body.contains("store_saves_total 0\n"), "{body}");
+    assert!(!body.contains(r#"route="/kv/{key}""#), "{body}");
+}
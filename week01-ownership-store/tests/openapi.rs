@@ -0,0 +1,91 @@
+mod test_server;
+
+use axum::{
+    Router,
+    body::{Body, to_bytes},
+    http::{Method, Request, StatusCode},
+};
+use serde_json::Value;
+use std::collections::BTreeSet;
+use tempfile::tempdir;
+use test_server::app_state;
+use tower::ServiceExt;
+use week01_ownership_store::{api, http, openapi};
+
+async fn get_json(app: Router, uri: &str) -> Value {
+    let req = Request::builder().uri(uri).body(Body::empty()).unwrap();
+    let resp = app.oneshot(req).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let bytes = to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+    serde_json::from_slice(&bytes).unwrap()
+}
+
+fn documented_operations(doc: &Value) -> BTreeSet<(String, String)> {
+    doc["paths"]
+        .as_object()
+        .unwrap()
+        .iter()
+        .flat_map(|(path, item)| {
+            item.as_object()
+                .unwrap()
+                .keys()
+                .map(move |method| (method.to_uppercase(), path.clone()))
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn serves_openapi_document() {
+    let doc = get_json(openapi::router(), "/openapi.json").await;
+
+    assert!(doc["openapi"].as_str().unwrap().starts_with("3."));
+    assert!(doc["components"]["schemas"]["StatsRequest"].is_object());
+    assert!(doc["components"]["schemas"]["KvPair"].is_object());
+}
+
+#[tokio::test]
+async fn document_covers_every_route_of_both_routers() {
+    let doc = get_json(openapi::router(), "/openapi.json").await;
+
+    // `app::router` also serves `/metrics`, `/healthz` and `/readyz`. They are
+    // for scrapers and probes rather than API clients, so like `/openapi.json`
+    // and `/docs` they are left out of the document
+    let expected: BTreeSet<(String, String)> = [
+        ("POST", "/v1/stats"),
+        ("POST", "/v1/uniq"),
+        ("POST", "/v1/grep"),
+        ("POST", "/v1/batch"),
+        ("GET", "/kv"),
+        ("GET", "/kv/{key}"),
+        ("POST", "/kv/{key}"),
+        ("DELETE", "/kv/{key}"),
+    ]
+    .into_iter()
+    .map(|(m, p)| (m.to_string(), p.to_string()))
+    .collect();
+    assert_eq!(documented_operations(&doc), expected);
+
+    // every documented operation must also be served: axum answers an
+    // unknown path with an empty 404 and a known path with a wrong method with 405
+    let dir = tempdir().unwrap();
+    let app = http::router().merge(api::router(app_state(&dir.path().join("data.json"))));
+
+    for (method, path) in expected {
+        let uri = path.replace("{key}", "probe");
+        let req = Request::builder()
+            .method(Method::from_bytes(method.as_bytes()).unwrap())
+            .uri(&uri)
+            .body(Body::empty())
+            .unwrap();
+        let resp = app.clone().oneshot(req).await.unwrap();
+        let status = resp.status();
+        let body = to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+
+        assert_ne!(status, StatusCode::METHOD_NOT_ALLOWED, "{method} {uri}");
+        assert!(
+            !(status == StatusCode::NOT_FOUND && body.is_empty()),
+            "{method} {uri} is not routed"
+        );
+    }
+}
@@ -0,0 +1,395 @@
+use serde_json::Value;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::Path;
+use std::process::{Child, ChildStderr, Command, ExitStatus, Output, Stdio};
+use tempfile::tempdir;
+
+fn store(args: &[&str], envs: &[(&str, &str)]) -> Output {
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_week01-ownership-store"));
+    for (key, _) in std::env::vars().filter(|(key, _)| key.starts_with("STORE_")) {
+        cmd.env_remove(key);
+    }
+    cmd.args(args).envs(envs.iter().copied()).output().unwrap()
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+#[test]
+fn invalid_listen_flag_fails_with_message() {
+    let output = store(&["--listen", "nowhere"], &[]);
+
+    assert_eq!(Some(2), output.status.code());
+    assert!(stderr(&output).contains("error: invalid listen address: nowhere"));
+}
+
+#[test]
+fn environment_variables_are_validated() {
+    let output = store(&[], &[("STORE_MAX_BODY", "huge")]);
+
+    assert_eq!(Some(2), output.status.code());
+    assert!(stderr(&output).contains("error: invalid max body: huge"));
+}
+
+#[test]
+fn config_file_errors_name_the_file() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("store.toml");
+    std::fs::write(&path, "listen = 3000\n").unwrap();
+
+    let output = store(&["--config", path.to_str().unwrap()], &[]);
+
+    assert_eq!(Some(2), output.status.code());
+    let err = stderr(&output);
+    assert!(err.contains("invalid config file"), "{err}");
+    assert!(err.contains("store.toml"), "{err}");
+
+    let output = store(&[], &[("STORE_CONFIG", "/no/such/store.toml")]);
+    assert!(stderr(&output).contains("cannot read config file /no/such/store.toml"));
+}
+
+#[test]
+fn occupied_address_is_reported() {
+    let dir = tempdir().unwrap();
+    let taken = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = taken.local_addr().unwrap().to_string();
+    let data_file = dir.path().join("data.json");
+
+    let output = store(
+        &["--data-file", data_file.to_str().unwrap()],
+        &[("STORE_LISTEN", &addr)],
+    );
+
+    assert_eq!(Some(1), output.status.code());
+    let err = stderr(&output);
+    assert!(err.contains("cannot listen"), "{err}");
+    assert!(err.contains(&addr), "{err}");
+}
+
+#[cfg(unix)]
+/// The server binary running with JSON logs on a free port.
+struct Running {
+    child: Child,
+    logs: BufReader<ChildStderr>,
+    addr: String,
+}
+
+#[cfg(unix)]
+impl Running {
+    fn start(data_file: &Path, args: &[&str]) -> Self {
+        Self::start_with_env(data_file, args, &[])
+    }
+
+    fn start_with_env(data_file: &Path, args: &[&str], envs: &[(&str, &str)]) -> Self {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_week01-ownership-store"))
+            .args(["--listen", "127.0.0.1:0", "--log-format", "json"])
+            .args(args)
+            .arg("--data-file")
+            .arg(data_file)
+            .envs(envs.iter().copied())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let mut logs = BufReader::new(child.stderr.take().unwrap());
+
+        let addr = loop {
+            let mut line = String::new();
+            assert_ne!(0, logs.read_line(&mut line).unwrap(), "server exited");
+            let log: Value = serde_json::from_str(&line).unwrap();
+            if log["message"] == "listening" {
+                break log["addr"].as_str().unwrap().to_string();
+            }
+        };
+
+        Running { child, logs, addr }
+    }
+
+    fn next_log(&mut self) -> Value {
+        let mut line = String::new();
+        assert_ne!(0, self.logs.read_line(&mut line).unwrap(), "server exited");
+        serde_json::from_str(&line).unwrap()
+    }
+
+    fn signal(&self, name: &str) {
+        let sent = Command::new("kill")
+            .args([name, &self.child.id().to_string()])
+            .status()
+            .unwrap();
+        assert!(sent.success());
+    }
+
+    /// Sends `head` (request line plus extra headers) and a JSON `body`.
+    fn request(&self, head: &str, body: &str) -> String {
+        let mut conn = std::net::TcpStream::connect(&self.addr).unwrap();
+        write!(
+            conn,
+            "{head}\r\nHost: test\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            body.len()
+        )
+        .unwrap();
+        let mut response = String::new();
+        conn.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    /// Sends SIGTERM and returns the exit status and the remaining log lines.
+    fn terminate(mut self) -> (ExitStatus, Vec<Value>) {
+        self.signal("-TERM");
+        let status = self.child.wait().unwrap();
+        let mut rest = String::new();
+        self.logs.read_to_string(&mut rest).unwrap();
+        let logs = rest
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        (status, logs)
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn sigterm_drains_and_flushes() {
+    let dir = tempdir().unwrap();
+    let data_file = dir.path().join("data.json");
+    let server = Running::start(&data_file, &[]);
+
+    let response = server.request("POST /kv/k HTTP/1.1\r\nX-Request-Id: req-42", r#"{"n":1}"#);
+    assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+    assert!(response.contains("x-request-id: req-42"), "{response}");
+
+    let (status, logs) = server.terminate();
+    assert!(status.success(), "{logs:?}");
+
+    let finished = logs
+        .iter()
+        .find(|log| log["message"] == "request finished")
+        .expect("request log line");
+    assert_eq!(200, finished["status"]);
+    assert!(finished["latency_ms"].is_number());
+    assert_eq!("POST", finished["span"]["method"]);
+    assert_eq!("/kv/{key}", finished["span"]["route"]);
+    assert_eq!("req-42", finished["span"]["request_id"]);
+    assert_eq!(200, finished["span"]["status"]);
+    assert!(finished["span"]["latency_ms"].is_number());
+
+    let shut_down = logs.last().unwrap();
+    assert_eq!("shut down", shut_down["message"]);
+    assert_eq!(true, shut_down["drained"]);
+    assert_eq!(1, shut_down["flushed_keys"]);
+    assert!(
+        std::fs::read_to_string(&data_file)
+            .unwrap()
+            .contains("\"n\": 1")
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn read_only_precedence_is_flag_then_env_then_file() {
+    let dir = tempdir().unwrap();
+    let data_file = dir.path().join("data.json");
+    let config = dir.path().join("store.toml");
+    std::fs::write(&config, "read_only = true\n").unwrap();
+    let config = config.to_str().unwrap();
+
+    let kv_write = |args: &[&str], envs: &[(&str, &str)]| {
+        let server = Running::start_with_env(&data_file, args, envs);
+        let response = server.request("POST /kv/k HTTP/1.1", "1");
+        let (status, _) = server.terminate();
+        assert!(status.success());
+        response[..12].to_string()
+    };
+
+    assert_eq!("HTTP/1.1 403", kv_write(&["--config", config], &[]));
+    assert_eq!(
+        "HTTP/1.1 200",
+        kv_write(&["--config", config], &[("STORE_READ_ONLY", "false")])
+    );
+    assert_eq!(
+        "HTTP/1.1 403",
+        kv_write(
+            &["--config", config, "--read-only"],
+            &[("STORE_READ_ONLY", "false")]
+        )
+    );
+    assert_eq!(
+        "HTTP/1.1 200",
+        kv_write(
+            &["--config", config, "--read-only=false"],
+            &[("STORE_READ_ONLY", "true")]
+        )
+    );
+}
+
+#[test]
+fn corrupt_data_file_refuses_to_start() {
+    let dir = tempdir().unwrap();
+    let data_file = dir.path().join("data.json");
+    std::fs::write(&data_file, "{\"a\": ").unwrap();
+
+    let output = store(
+        &[
+            "--listen",
+            "127.0.0.1:0",
+            "--data-file",
+            data_file.to_str().unwrap(),
+        ],
+        &[],
+    );
+
+    assert_eq!(Some(1), output.status.code());
+    assert!(stderr(&output).contains("cannot load data file"));
+    assert_eq!("{\"a\": ", std::fs::read_to_string(&data_file).unwrap());
+}
+
+#[cfg(unix)]
+#[test]
+fn corrupt_data_file_can_start_read_only() {
+    let dir = tempdir().unwrap();
+    let data_file = dir.path().join("data.json");
+    std::fs::write(&data_file, "{\"a\": ").unwrap();
+    let server = Running::start(&data_file, &["--on-load-error", "read-only"]);
+
+    assert!(
+        server
+            .request("GET /healthz HTTP/1.1", "")
+            .starts_with("HTTP/1.1 200")
+    );
+    let ready = server.request("GET /readyz HTTP/1.1", "");
+    assert!(ready.starts_with("HTTP/1.1 503"), "{ready}");
+    assert!(
+        server
+            .request("DELETE /kv/a HTTP/1.1", "")
+            .starts_with("HTTP/1.1 403")
+    );
+
+    let (status, _) = server.terminate();
+    assert!(status.success());
+    assert_eq!("{\"a\": ", std::fs::read_to_string(&data_file).unwrap());
+}
+
+#[cfg(unix)]
+#[test]
+fn corrupt_data_file_can_be_quarantined() {
+    let dir = tempdir().unwrap();
+    let data_file = dir.path().join("data.json");
+    std::fs::write(&data_file, "{\"a\": ").unwrap();
+    let server = Running::start(&data_file, &["--on-load-error", "quarantine"]);
+
+    let response = server.request("POST /kv/b HTTP/1.1", "2");
+    assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+    let (status, _) = server.terminate();
+    assert!(status.success());
+
+    let saved: Value = serde_json::from_str(&std::fs::read_to_string(&data_file).unwrap()).unwrap();
+    assert_eq!(serde_json::json!({"b": 2}), saved);
+    let quarantined: Vec<_> = std::fs::read_dir(dir.path())
+        .unwrap()
+        .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+        .filter(|name| name.starts_with("data.json.corrupt-"))
+        .collect();
+    assert_eq!(1, quarantined.len());
+    assert_eq!(
+        "{\"a\": ",
+        std::fs::read_to_string(dir.path().join(&quarantined[0])).unwrap()
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn corrupt_data_file_can_fall_back_to_a_backup() {
+    let dir = tempdir().unwrap();
+    let data_file = dir.path().join("data.json");
+    std::fs::write(&data_file, "{\"a\": ").unwrap();
+    std::fs::write(dir.path().join("data.json.bak-nightly"), r#"{"a": 1}"#).unwrap();
+    let server = Running::start(&data_file, &["--on-load-error", "backup"]);
+
+    let response = server.request("GET /kv/a HTTP/1.1", "");
+    assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+    assert!(response.ends_with(r#"{"key":"a","value":1}"#), "{response}");
+    let ready = server.request("GET /readyz HTTP/1.1", "");
+    assert!(ready.starts_with("HTTP/1.1 200"), "{ready}");
+
+    let (status, logs) = server.terminate();
+    assert!(status.success(), "{logs:?}");
+    let saved: Value = serde_json::from_str(&std::fs::read_to_string(&data_file).unwrap()).unwrap();
+    assert_eq!(serde_json::json!({"a": 1}), saved);
+}
+
+#[cfg(unix)]
+#[test]
+fn https_and_unix_socket_listeners() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = tempdir().unwrap();
+    let data_file = dir.path().join("data.json");
+    let cert = dir.path().join("cert.pem");
+    let key = dir.path().join("key.pem");
+    let socket = dir.path().join("store.sock");
+    let certified = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+    std::fs::write(&cert, certified.cert.pem()).unwrap();
+    std::fs::write(&key, certified.signing_key.serialize_pem()).unwrap();
+
+    let mut server = Running::start(
+        &data_file,
+        &[
+            "--tls-cert",
+            cert.to_str().unwrap(),
+            "--tls-key",
+            key.to_str().unwrap(),
+            "--unix-socket",
+            socket.to_str().unwrap(),
+            "--unix-socket-mode",
+            "600",
+        ],
+    );
+    let listening = server.next_log();
+    assert_eq!("listening", listening["message"]);
+    assert_eq!(socket.to_str().unwrap(), listening["socket"]);
+    let mode = std::fs::metadata(&socket).unwrap().permissions().mode();
+    assert_eq!(0o600, mode & 0o777);
+
+    server.signal("-HUP");
+    assert_eq!("reloaded TLS certificate", server.next_log()["message"]);
+
+    let mut conn = std::os::unix::net::UnixStream::connect(&socket).unwrap();
+    write!(
+        conn,
+        "GET /healthz HTTP/1.1\r\nHost: store\r\nConnection: close\r\n\r\n"
+    )
+    .unwrap();
+    let mut response = String::new();
+    conn.read_to_string(&mut response).unwrap();
+    assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+
+    let (status, logs) = server.terminate();
+    assert!(status.success(), "{logs:?}");
+    assert!(!socket.exists());
+}
+
+#[test]
+fn missing_tls_certificate_is_reported() {
+    let dir = tempdir().unwrap();
+    let data_file = dir.path().join("data.json");
+    let cert = dir.path().join("cert.pem");
+
+    let output = store(
+        &[
+            "--listen",
+            "127.0.0.1:0",
+            "--data-file",
+            data_file.to_str().unwrap(),
+            "--tls-cert",
+            cert.to_str().unwrap(),
+            "--tls-key",
+            cert.to_str().unwrap(),
+        ],
+        &[],
+    );
+
+    assert_eq!(Some(1), output.status.code());
+    let err = stderr(&output);
+    assert!(err.contains("cannot load TLS certificate"), "{err}");
+    assert!(err.contains("cert.pem"), "{err}");
+}
@@ -0,0 +1,139 @@
+mod test_server;
+
+use std::time::Duration;
+use tempfile::tempdir;
+use test_server::app_state;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::oneshot;
+use week01_ownership_store::app::{self, AppConfig};
+use week01_ownership_store::server::{self, ShutdownOptions};
+use week01_ownership_store::storage::file;
+
+const BODY: &str = r#"{"text":"a b\n"}"#;
+
+/// Sends the headers and the first half of a `/v1/stats` request, leaving
+/// the request in flight.
+async fn start_request(addr: std::net::SocketAddr) -> TcpStream {
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+    let head = format!(
+        "POST /v1/stats HTTP/1.1\r\nHost: test\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        BODY.len()
+    );
+    stream.write_all(head.as_bytes()).await.unwrap();
+    stream.write_all(&BODY.as_bytes()[..5]).await.unwrap();
+    stream
+}
+
+async fn wait_until_refused(addr: std::net::SocketAddr) {
+    for _ in 0..100 {
+        if TcpStream::connect(addr).await.is_err() {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    panic!("server still accepts connections");
+}
+
+#[tokio::test]
+async fn in_flight_request_finishes_and_store_is_flushed() {
+    let dir = tempdir().unwrap();
+    let state = app_state(&dir.path().join("data.json"));
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let (stop_tx, stop_rx) = oneshot::channel::<()>();
+
+    let app = app::router(state.clone(), &AppConfig::default());
+    let server = tokio::spawn(server::serve(
+        [listener.into()],
+        app,
+        state.clone(),
+        ShutdownOptions::default(),
+        async {
+            let _ = stop_rx.await;
+        },
+    ));
+
+    // a change that has not been saved yet
+    state
+        .store
+        .write()
+        .await
+        .insert("unsaved".to_string(), serde_json::json!(1));
+
+    let mut in_flight = start_request(addr).await;
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    stop_tx.send(()).unwrap();
+
+    wait_until_refused(addr).await;
+
+    in_flight.write_all(&BODY.as_bytes()[5..]).await.unwrap();
+    let mut response = String::new();
+    in_flight.read_to_string(&mut response).await.unwrap();
+    assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+    assert!(response.contains(r#""words":2"#), "{response}");
+
+    let summary = server.await.unwrap().unwrap();
+    assert!(summary.drained);
+    assert_eq!(Some(1), summary.flushed_keys);
+
+    let saved = file::load_from_file(&state.data_file).await.unwrap();
+    assert_eq!(Some(&serde_json::json!(1)), saved.get("unsaved"));
+}
+
+#[tokio::test]
+async fn drain_timeout_drops_stuck_requests_but_still_flushes() {
+    let dir = tempdir().unwrap();
+    let state = app_state(&dir.path().join("data.json"));
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let (stop_tx, stop_rx) = oneshot::channel::<()>();
+
+    let app = app::router(state.clone(), &AppConfig::default());
+    let options = ShutdownOptions {
+        drain_timeout: Duration::from_millis(100),
+        flush: true,
+    };
+    let server = tokio::spawn(server::serve(
+        [listener.into()],
+        app,
+        state.clone(),
+        options,
+        async {
+            let _ = stop_rx.await;
+        },
+    ));
+
+    // never finishes its body
+    let _stuck = start_request(addr).await;
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    stop_tx.send(()).unwrap();
+
+    let summary = tokio::time::timeout(Duration::from_secs(5), server)
+        .await
+        .expect("shutdown did not honor the drain timeout")
+        .unwrap()
+        .unwrap();
+    assert!(!summary.drained);
+    assert_eq!(Some(0), summary.flushed_keys);
+    assert!(dir.path().join("data.json").exists());
+}
+
+#[tokio::test]
+async fn no_flush_leaves_data_file_alone() {
+    let dir = tempdir().unwrap();
+    let state = app_state(&dir.path().join("data.json"));
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+
+    let app = app::router(state.clone(), &AppConfig::default());
+    let options = ShutdownOptions {
+        flush: false,
+        ..ShutdownOptions::default()
+    };
+    let summary = server::serve([listener.into()], app, state, options, async {})
+        .await
+        .unwrap();
+
+    assert_eq!(None, summary.flushed_keys);
+    assert!(!dir.path().join("data.json").exists());
+}
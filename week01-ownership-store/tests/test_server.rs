@@ -1,6 +1,12 @@
+// Shared by several test crates; each uses only some of the helpers.
+#![allow(dead_code)]
+
 use axum::Router;
 use std::net::SocketAddr;
+use std::path::Path;
 use tokio::task::JoinHandle;
+use week01_ownership_store::api::state::AppState;
+use week01_ownership_store::storage::new_store;
 
 pub async fn spawn_app(app: Router) -> (SocketAddr, JoinHandle<()>) {
     let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
@@ -15,3 +21,12 @@ pub async fn spawn_app(app: Router) -> (SocketAddr, JoinHandle<()>) {
 
     (addr, handle)
 }
+
+/// An empty store that saves to `data_file`, with its own metrics.
+pub fn app_state(data_file: &Path) -> AppState {
+    AppState {
+        store: new_store(),
+        data_file: data_file.to_string_lossy().into_owned(),
+        metrics: Default::default(),
+    }
+}
@@ -1,2 +1,3 @@
 pub mod errors;
+pub mod lines;
 pub mod stats;
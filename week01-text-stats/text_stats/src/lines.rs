@@ -0,0 +1,56 @@
+/// Splits text into `(line, ending)` pairs, recognizing `\r\n`, `\n` and a
+/// lone `\r`. The last pair has an empty ending when the text does not end
+/// with a line break.
+pub struct LinesWithEndings<'a>(&'a str);
+
+impl<'a> LinesWithEndings<'a> {
+    pub fn new(text: &'a str) -> Self {
+        LinesWithEndings(text)
+    }
+}
+
+impl<'a> Iterator for LinesWithEndings<'a> {
+    type Item = (&'a str, &'a str);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.0.is_empty() {
+            return None;
+        }
+
+        let rest = self.0;
+        let (line, ending) = match rest.find(['\r', '\n']) {
+            Some(i) if rest[i..].starts_with("\r\n") => (&rest[..i], &rest[i..i + 2]),
+            Some(i) => (&rest[..i], &rest[i..i + 1]),
+            None => (rest, ""),
+        };
+        self.0 = &rest[line.len() + ending.len()..];
+
+        Some((line, ending))
+    }
+}
+
+/// The lines of `text` without their endings. Unlike [`str::lines`], a lone
+/// `\r` also ends a line.
+pub fn lines(text: &str) -> impl Iterator<Item = &str> {
+    LinesWithEndings::new(text).map(|(line, _)| line)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LinesWithEndings, lines};
+
+    #[test]
+    fn every_ending_style_splits() {
+        let pairs: Vec<_> = LinesWithEndings::new("a\r\nb\nc\rd").collect();
+        assert_eq!(
+            vec![("a", "\r\n"), ("b", "\n"), ("c", "\r"), ("d", "")],
+            pairs
+        );
+    }
+
+    #[test]
+    fn trailing_break_adds_no_empty_line() {
+        assert_eq!(vec!["a", ""], lines("a\n\r\n").collect::<Vec<_>>());
+        assert_eq!(0, lines("").count());
+    }
+}
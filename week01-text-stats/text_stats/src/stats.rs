@@ -1,6 +1,7 @@
 use std::collections::HashMap;
 
 use crate::errors::StatsError;
+use crate::lines::lines;
 
 pub struct TextStats {
     pub lines: usize,
@@ -36,14 +37,14 @@ pub fn analyze(text: &str) -> Result<TextStats, StatsError> {
         .map(|(word, _)| word.to_string());
 
     let out = TextStats {
-        lines: text.matches("\n").count() + 1,
+        lines: lines(text).count(),
         words: text.split_whitespace().count(),
         chars: text.chars().count(),
-        non_empty_lines: text.lines().filter(|l| !l.trim().is_empty()).count(),
-        top_word: top_word,
+        non_empty_lines: lines(text).filter(|l| !l.trim().is_empty()).count(),
+        top_word,
     };
 
-    return Ok(out);
+    Ok(out)
 }
 
 #[cfg(test)]
@@ -62,6 +63,15 @@ mod tests {
         assert_eq!(2, result.lines);
     }
 
+    #[test]
+    fn crlf_and_trailing_break_count_like_lf() {
+        let lf = analyze("line1\nline2\n").unwrap();
+        let crlf = analyze("line1\r\nline2\r\n").unwrap();
+        assert_eq!(2, lf.lines);
+        assert_eq!(2, crlf.lines);
+        assert_eq!(2, analyze("line1\rline2").unwrap().lines);
+    }
+
     #[test]
     fn three_words() {
         let result = analyze("word1 word2 word3").unwrap();
@@ -0,0 +1,98 @@
+use crate::encoding::EncodingChoice;
+use crate::errors::TextkitError;
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+/// How many leading bytes are inspected when looking for binary content.
+pub const SAMPLE_SIZE: usize = 8192;
+
+/// What to do with input that looks binary, modelled on GNU grep's
+/// `--binary-files`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BinaryFiles {
+    /// `grep` prints "Binary file X matches"; other commands refuse the input.
+    #[default]
+    Binary,
+    /// Process the input as text anyway.
+    Text,
+    /// Assume binary input never matches; other commands skip it silently.
+    WithoutMatch,
+    /// Skip binary input with a note on stderr.
+    Skip,
+}
+
+impl FromStr for BinaryFiles {
+    type Err = TextkitError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "binary" => Ok(BinaryFiles::Binary),
+            "text" => Ok(BinaryFiles::Text),
+            "without-match" => Ok(BinaryFiles::WithoutMatch),
+            "skip" => Ok(BinaryFiles::Skip),
+            _ => Err(TextkitError::InvalidArgument(format!(
+                "unknown binary-files mode: {s} (expected binary, text, without-match or skip)"
+            ))),
+        }
+    }
+}
+
+/// Reports whether `bytes` look binary: a NUL byte within the first
+/// [`SAMPLE_SIZE`] bytes. Input with a BOM, or read as UTF-16, is always text
+/// because UTF-16 legitimately contains NUL bytes.
+pub fn is_binary(bytes: &[u8], encoding: EncodingChoice) -> bool {
+    if let EncodingChoice::Fixed(enc) = encoding
+        && (enc == UTF_16LE || enc == UTF_16BE)
+    {
+        return false;
+    }
+
+    if Encoding::for_bom(bytes).is_some() {
+        return false;
+    }
+
+    bytes[..bytes.len().min(SAMPLE_SIZE)].contains(&0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BinaryFiles, SAMPLE_SIZE, is_binary};
+    use crate::encoding::EncodingChoice;
+
+    #[test]
+    fn nul_byte_in_prefix_is_binary() {
+        assert!(is_binary(b"\x7FELF\x02\x01\x00\x00", EncodingChoice::Auto));
+        assert!(!is_binary(b"plain text\n", EncodingChoice::Auto));
+        assert!(!is_binary(b"", EncodingChoice::Auto));
+    }
+
+    #[test]
+    fn nul_after_sample_is_not_inspected() {
+        let mut bytes = vec![b'a'; SAMPLE_SIZE];
+        bytes.push(0);
+        assert!(!is_binary(&bytes, EncodingChoice::Auto));
+    }
+
+    #[test]
+    fn utf16_is_never_binary() {
+        let with_bom = [0xFF, 0xFE, b'h', 0, b'i', 0];
+        assert!(!is_binary(&with_bom, EncodingChoice::Auto));
+
+        let without_bom = [b'h', 0, b'i', 0];
+        let utf16: EncodingChoice = "utf-16le".parse().unwrap();
+        assert!(!is_binary(&without_bom, utf16));
+        assert!(is_binary(&without_bom, EncodingChoice::Auto));
+    }
+
+    #[test]
+    fn parses_gnu_style_modes() {
+        assert_eq!(
+            BinaryFiles::WithoutMatch,
+            "without-match".parse::<BinaryFiles>().unwrap()
+        );
+        assert_eq!(BinaryFiles::Skip, "skip".parse::<BinaryFiles>().unwrap());
+        assert!("maybe".parse::<BinaryFiles>().is_err());
+    }
+}
@@ -0,0 +1,98 @@
+use crate::errors::TextkitError;
+use std::ops::Range;
+use std::str::FromStr;
+
+// SGR sequences matching GNU grep's default GREP_COLORS.
+pub const MATCH: &str = "\x1b[01;31m";
+pub const FILE_NAME: &str = "\x1b[35m";
+pub const LINE_NUMBER: &str = "\x1b[32m";
+pub const SEPARATOR: &str = "\x1b[36m";
+const RESET: &str = "\x1b[0m";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ColorChoice {
+    /// Color only when writing to a terminal and `NO_COLOR` is not set.
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    /// Decides whether to emit ANSI colors. `NO_COLOR` only affects `auto`, so
+    /// an explicit `--color=always` still wins.
+    pub fn enabled(self, is_terminal: bool, no_color: bool) -> bool {
+        match self {
+            ColorChoice::Auto => is_terminal && !no_color,
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+        }
+    }
+}
+
+impl FromStr for ColorChoice {
+    type Err = TextkitError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(ColorChoice::Auto),
+            "always" => Ok(ColorChoice::Always),
+            "never" => Ok(ColorChoice::Never),
+            _ => Err(TextkitError::InvalidArgument(format!(
+                "unknown color mode: {s} (expected auto, always or never)"
+            ))),
+        }
+    }
+}
+
+/// Wraps `text` in the given SGR sequence and a reset.
+pub fn paint(text: &str, style: &str) -> String {
+    format!("{style}{text}{RESET}")
+}
+
+/// Colors the byte ranges `spans` of `line` as matches. Spans must be sorted,
+/// non-overlapping and on char boundaries, as produced by the grep engine;
+/// empty spans are left alone.
+pub fn highlight(line: &str, spans: &[Range<usize>]) -> String {
+    let mut out = String::with_capacity(line.len() + spans.len() * 16);
+    let mut pos = 0;
+
+    for span in spans.iter().filter(|span| !span.is_empty()) {
+        out.push_str(&line[pos..span.start]);
+        out.push_str(&paint(&line[span.clone()], MATCH));
+        pos = span.end;
+    }
+    out.push_str(&line[pos..]);
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ColorChoice, highlight, paint};
+
+    #[test]
+    fn auto_needs_a_terminal_and_no_no_color() {
+        assert!(ColorChoice::Auto.enabled(true, false));
+        assert!(!ColorChoice::Auto.enabled(false, false));
+        assert!(!ColorChoice::Auto.enabled(true, true));
+        assert!(ColorChoice::Always.enabled(false, true));
+        assert!(!ColorChoice::Never.enabled(true, false));
+    }
+
+    #[test]
+    fn highlights_each_span() {
+        assert_eq!(
+            "a \x1b[01;31mbb\x1b[0m c \x1b[01;31mbb\x1b[0m",
+            highlight("a bb c bb", &[2..4, 7..9])
+        );
+        assert_eq!("plain", highlight("plain", &[]));
+        assert_eq!("ab", highlight("ab", &[0..0, 1..1, 2..2]));
+    }
+
+    #[test]
+    fn paint_resets_after_text() {
+        assert_eq!("\x1b[32m12\x1b[0m", paint("12", super::LINE_NUMBER));
+        assert!("sometimes".parse::<ColorChoice>().is_err());
+    }
+}
@@ -0,0 +1,374 @@
+use crate::binary::BinaryFiles;
+use crate::encoding::EncodingChoice;
+use crate::errors::TextkitError;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+pub const FILE_NAME: &str = "textkit.toml";
+
+/// Contents of a `textkit.toml`: per-subcommand defaults plus named presets.
+///
+/// Every setting is optional so that an unset value falls through to the
+/// next layer (CLI flag, then preset, then section default, then built-in).
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub encoding: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lossy: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub binary_files: Option<BinaryFiles>,
+    pub grep: GrepSection,
+    pub uniq: UniqSection,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GrepSection {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line_number: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ignore_case: Option<bool>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub presets: BTreeMap<String, GrepPreset>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GrepPreset {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line_number: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ignore_case: Option<bool>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct UniqSection {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub all: Option<bool>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub presets: BTreeMap<String, UniqPreset>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct UniqPreset {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub all: Option<bool>,
+}
+
+/// Flags given on the command line for `grep`; `None` means "not given".
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GrepFlags {
+    pub line_number: Option<bool>,
+    pub ignore_case: Option<bool>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GrepSettings {
+    pub line_number: bool,
+    pub ignore_case: bool,
+}
+
+/// Flags given on the command line for `uniq`; `None` means "not given".
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UniqFlags {
+    pub all: Option<bool>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UniqSettings {
+    pub all: bool,
+}
+
+impl Config {
+    pub fn parse(content: &str) -> Result<Self, TextkitError> {
+        let config: Config =
+            toml::from_str(content).map_err(|e| TextkitError::Config(e.to_string()))?;
+
+        // fail on a bad label now rather than on the first command that reads input
+        if let Some(label) = &config.encoding {
+            label
+                .parse::<EncodingChoice>()
+                .map_err(|e| TextkitError::Config(e.to_string()))?;
+        }
+
+        Ok(config)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, TextkitError> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path)?;
+        Self::parse(&content).map_err(|e| match e {
+            TextkitError::Config(msg) => TextkitError::Config(format!("{}: {msg}", path.display())),
+            other => other,
+        })
+    }
+
+    pub fn encoding(&self, flag: Option<EncodingChoice>) -> Result<EncodingChoice, TextkitError> {
+        match (flag, &self.encoding) {
+            (Some(choice), _) => Ok(choice),
+            (None, Some(label)) => label.parse(),
+            (None, None) => Ok(EncodingChoice::Auto),
+        }
+    }
+
+    pub fn lossy(&self, flag: Option<bool>) -> bool {
+        flag.or(self.lossy).unwrap_or(false)
+    }
+
+    pub fn binary_files(&self, flag: Option<BinaryFiles>) -> BinaryFiles {
+        flag.or(self.binary_files).unwrap_or_default()
+    }
+
+    /// Resolves `grep` settings: CLI flag, then preset, then `[grep]` default.
+    pub fn grep(
+        &self,
+        flags: GrepFlags,
+        preset: Option<&str>,
+    ) -> Result<GrepSettings, TextkitError> {
+        let preset = match preset {
+            Some(name) => lookup_preset(&self.grep.presets, "grep", name)?.clone(),
+            None => GrepPreset::default(),
+        };
+
+        Ok(GrepSettings {
+            line_number: flags
+                .line_number
+                .or(preset.line_number)
+                .or(self.grep.line_number)
+                .unwrap_or(false),
+            ignore_case: flags
+                .ignore_case
+                .or(preset.ignore_case)
+                .or(self.grep.ignore_case)
+                .unwrap_or(false),
+        })
+    }
+
+    /// Resolves `uniq` settings: CLI flag, then preset, then `[uniq]` default.
+    pub fn uniq(
+        &self,
+        flags: UniqFlags,
+        preset: Option<&str>,
+    ) -> Result<UniqSettings, TextkitError> {
+        let preset = match preset {
+            Some(name) => lookup_preset(&self.uniq.presets, "uniq", name)?.clone(),
+            None => UniqPreset::default(),
+        };
+
+        Ok(UniqSettings {
+            all: flags.all.or(preset.all).or(self.uniq.all).unwrap_or(false),
+        })
+    }
+
+    /// The configuration with every unset default replaced by its built-in value.
+    pub fn effective(&self) -> Config {
+        Config {
+            encoding: Some(self.encoding.clone().unwrap_or_else(|| "auto".to_string())),
+            lossy: Some(self.lossy(None)),
+            binary_files: Some(self.binary_files(None)),
+            grep: GrepSection {
+                line_number: Some(self.grep.line_number.unwrap_or(false)),
+                ignore_case: Some(self.grep.ignore_case.unwrap_or(false)),
+                presets: self.grep.presets.clone(),
+            },
+            uniq: UniqSection {
+                all: Some(self.uniq.all.unwrap_or(false)),
+                presets: self.uniq.presets.clone(),
+            },
+        }
+    }
+
+    pub fn to_toml(&self) -> Result<String, TextkitError> {
+        toml::to_string(self).map_err(|e| TextkitError::Config(e.to_string()))
+    }
+}
+
+fn lookup_preset<'a, T>(
+    presets: &'a BTreeMap<String, T>,
+    command: &str,
+    name: &str,
+) -> Result<&'a T, TextkitError> {
+    presets
+        .get(name)
+        .ok_or_else(|| TextkitError::InvalidArgument(format!("unknown {command} preset: {name}")))
+}
+
+/// Finds the config file: `textkit.toml` in `cwd`, then
+/// `$XDG_CONFIG_HOME/textkit/textkit.toml` (with `$HOME/.config` as the XDG
+/// default).
+pub fn find(cwd: &Path, xdg_config_home: Option<&Path>, home: Option<&Path>) -> Option<PathBuf> {
+    let local = cwd.join(FILE_NAME);
+    if local.is_file() {
+        return Some(local);
+    }
+
+    let config_home = match xdg_config_home {
+        Some(dir) if dir.is_absolute() => dir.to_path_buf(),
+        _ => home?.join(".config"),
+    };
+    let global = config_home.join("textkit").join(FILE_NAME);
+
+    global.is_file().then_some(global)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Config, GrepFlags, GrepSettings, UniqFlags, find};
+    use crate::binary::BinaryFiles;
+    use crate::encoding::EncodingChoice;
+    use crate::errors::TextkitError;
+    use std::fs;
+    use tempfile::tempdir;
+
+    const SAMPLE: &str = r#"
+encoding = "windows-1251"
+
+[grep]
+line_number = true
+
+[grep.presets.errors]
+ignore_case = true
+line_number = false
+
+[uniq.presets.dedupe]
+all = true
+"#;
+
+    #[test]
+    fn cli_flags_override_preset_and_defaults() {
+        let config = Config::parse(SAMPLE).unwrap();
+
+        let defaults = config.grep(GrepFlags::default(), None).unwrap();
+        assert_eq!(
+            GrepSettings {
+                line_number: true,
+                ignore_case: false
+            },
+            defaults
+        );
+
+        let preset = config.grep(GrepFlags::default(), Some("errors")).unwrap();
+        assert_eq!(
+            GrepSettings {
+                line_number: false,
+                ignore_case: true
+            },
+            preset
+        );
+
+        let flags = GrepFlags {
+            line_number: Some(true),
+            ignore_case: Some(false),
+        };
+        let overridden = config.grep(flags, Some("errors")).unwrap();
+        assert_eq!(
+            GrepSettings {
+                line_number: true,
+                ignore_case: false
+            },
+            overridden
+        );
+    }
+
+    #[test]
+    fn uniq_preset_applies() {
+        let config = Config::parse(SAMPLE).unwrap();
+        assert!(!config.uniq(UniqFlags::default(), None).unwrap().all);
+        assert!(
+            config
+                .uniq(UniqFlags::default(), Some("dedupe"))
+                .unwrap()
+                .all
+        );
+    }
+
+    #[test]
+    fn unknown_preset_is_invalid_argument() {
+        let config = Config::parse(SAMPLE).unwrap();
+        let err = config
+            .grep(GrepFlags::default(), Some("nope"))
+            .err()
+            .unwrap();
+        assert!(matches!(err, TextkitError::InvalidArgument(_)));
+    }
+
+    #[test]
+    fn encoding_falls_back_to_config_then_auto() {
+        let config = Config::parse(SAMPLE).unwrap();
+        let utf8: EncodingChoice = "utf-8".parse().unwrap();
+        let cp1251: EncodingChoice = "windows-1251".parse().unwrap();
+
+        assert_eq!(utf8, config.encoding(Some(utf8)).unwrap());
+        assert_eq!(cp1251, config.encoding(None).unwrap());
+        assert_eq!(
+            EncodingChoice::Auto,
+            Config::default().encoding(None).unwrap()
+        );
+    }
+
+    #[test]
+    fn binary_files_flag_overrides_config() {
+        let config = Config::parse("binary_files = \"skip\"\n").unwrap();
+        assert_eq!(BinaryFiles::Skip, config.binary_files(None));
+        assert_eq!(
+            BinaryFiles::Text,
+            config.binary_files(Some(BinaryFiles::Text))
+        );
+        assert_eq!(BinaryFiles::Binary, Config::default().binary_files(None));
+    }
+
+    #[test]
+    fn parse_rejects_unknown_keys_and_bad_encoding() {
+        let typo = Config::parse("[grep]\nignorecase = true\n").err().unwrap();
+        assert!(matches!(typo, TextkitError::Config(_)));
+
+        let bad = Config::parse("encoding = \"klingon\"\n").err().unwrap();
+        assert!(matches!(bad, TextkitError::Config(_)));
+    }
+
+    #[test]
+    fn effective_fills_builtin_defaults() {
+        let shown = Config::default().effective().to_toml().unwrap();
+        assert!(shown.contains("encoding = \"auto\""));
+        assert!(shown.contains("binary_files = \"binary\""));
+        assert!(shown.contains("[grep]\nline_number = false\nignore_case = false"));
+        assert!(shown.contains("[uniq]\nall = false"));
+    }
+
+    #[test]
+    fn find_prefers_cwd_then_xdg_then_home() {
+        let cwd = tempdir().unwrap();
+        let xdg = tempdir().unwrap();
+        let home = tempdir().unwrap();
+
+        assert_eq!(None, find(cwd.path(), Some(xdg.path()), Some(home.path())));
+
+        let home_file = home.path().join(".config/textkit/textkit.toml");
+        fs::create_dir_all(home_file.parent().unwrap()).unwrap();
+        fs::write(&home_file, "").unwrap();
+        assert_eq!(
+            Some(home_file.clone()),
+            find(cwd.path(), None, Some(home.path()))
+        );
+
+        let xdg_file = xdg.path().join("textkit/textkit.toml");
+        fs::create_dir_all(xdg_file.parent().unwrap()).unwrap();
+        fs::write(&xdg_file, "").unwrap();
+        assert_eq!(
+            Some(xdg_file),
+            find(cwd.path(), Some(xdg.path()), Some(home.path()))
+        );
+
+        let local = cwd.path().join("textkit.toml");
+        fs::write(&local, "").unwrap();
+        assert_eq!(
+            Some(local),
+            find(cwd.path(), Some(xdg.path()), Some(home.path()))
+        );
+    }
+}
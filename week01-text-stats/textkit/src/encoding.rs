@@ -0,0 +1,183 @@
+use crate::errors::TextkitError;
+use encoding_rs::{DecoderResult, Encoding, UTF_8, WINDOWS_1251, WINDOWS_1252};
+use std::str::FromStr;
+
+/// How input bytes are turned into text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EncodingChoice {
+    /// Sniff a BOM, then try UTF-8, then guess a single-byte encoding.
+    Auto,
+    /// Always decode with the given encoding (a matching BOM is still stripped).
+    Fixed(&'static Encoding),
+}
+
+impl FromStr for EncodingChoice {
+    type Err = TextkitError;
+
+    fn from_str(label: &str) -> Result<Self, Self::Err> {
+        if label.eq_ignore_ascii_case("auto") {
+            return Ok(EncodingChoice::Auto);
+        }
+
+        Encoding::for_label(label.as_bytes())
+            .map(EncodingChoice::Fixed)
+            .ok_or_else(|| TextkitError::InvalidArgument(format!("unknown encoding: {label}")))
+    }
+}
+
+pub struct Decoded {
+    pub text: String,
+    pub encoding: &'static Encoding,
+    pub invalid_sequences: usize,
+    /// Whether a byte order mark was found (and stripped) at the start.
+    pub bom: bool,
+}
+
+/// Decodes `bytes` into a `String`.
+///
+/// With `lossy` every malformed sequence is replaced by U+FFFD and counted;
+/// without it the first malformed sequence is an error.
+pub fn decode(bytes: &[u8], choice: EncodingChoice, lossy: bool) -> Result<Decoded, TextkitError> {
+    let (encoding, bom_len) = match (choice, Encoding::for_bom(bytes)) {
+        (EncodingChoice::Auto, Some((enc, len))) => (enc, len),
+        (EncodingChoice::Auto, None) => (detect(bytes), 0),
+        (EncodingChoice::Fixed(enc), Some((bom_enc, len))) if bom_enc == enc => (enc, len),
+        (EncodingChoice::Fixed(enc), _) => (enc, 0),
+    };
+
+    let mut decoder = encoding.new_decoder_without_bom_handling();
+    let mut text = String::new();
+    let mut invalid_sequences = 0;
+    let mut input = &bytes[bom_len..];
+    let mut consumed = bom_len;
+
+    loop {
+        if let Some(needed) = decoder.max_utf8_buffer_length_without_replacement(input.len()) {
+            text.reserve(needed);
+        }
+
+        let (result, read) = decoder.decode_to_string_without_replacement(input, &mut text, true);
+        input = &input[read..];
+        consumed += read;
+
+        match result {
+            DecoderResult::InputEmpty => break,
+            DecoderResult::OutputFull => {}
+            DecoderResult::Malformed(bad, extra) => {
+                if !lossy {
+                    return Err(TextkitError::Decode {
+                        encoding: encoding.name(),
+                        offset: consumed - bad as usize - extra as usize,
+                    });
+                }
+                invalid_sequences += 1;
+                text.push(char::REPLACEMENT_CHARACTER);
+            }
+        }
+    }
+
+    Ok(Decoded {
+        text,
+        encoding,
+        invalid_sequences,
+        bom: bom_len > 0,
+    })
+}
+
+/// Guesses the encoding of BOM-less input.
+///
+/// Input that is mostly valid UTF-8 is treated as UTF-8, so a few stray
+/// bytes in an otherwise UTF-8 log do not flip the whole file. Anything else
+/// is assumed to be Windows-1251 when high bytes outnumber ASCII letters
+/// (Cyrillic text) and Windows-1252 (a superset of Latin-1) otherwise.
+pub fn detect(bytes: &[u8]) -> &'static Encoding {
+    let mut multibyte_chars = 0;
+    let mut invalid_chunks = 0;
+    for chunk in bytes.utf8_chunks() {
+        multibyte_chars += chunk.valid().chars().filter(|c| !c.is_ascii()).count();
+        if !chunk.invalid().is_empty() {
+            invalid_chunks += 1;
+        }
+    }
+
+    if invalid_chunks == 0 || multibyte_chars > invalid_chunks {
+        return UTF_8;
+    }
+
+    let high = bytes.iter().filter(|b| **b >= 0x80).count();
+    let ascii_letters = bytes.iter().filter(|b| b.is_ascii_alphabetic()).count();
+
+    if high > ascii_letters {
+        WINDOWS_1251
+    } else {
+        WINDOWS_1252
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{EncodingChoice, decode, detect};
+    use crate::errors::TextkitError;
+    use encoding_rs::{UTF_8, UTF_16BE, UTF_16LE, WINDOWS_1251, WINDOWS_1252};
+
+    #[test]
+    fn auto_detects_plain_utf8() {
+        let d = decode("héllo\n".as_bytes(), EncodingChoice::Auto, false).unwrap();
+        assert_eq!(UTF_8, d.encoding);
+        assert_eq!("héllo\n", d.text);
+        assert_eq!(0, d.invalid_sequences);
+    }
+
+    #[test]
+    fn auto_sniffs_utf16_boms() {
+        let le = [0xFF, 0xFE, b'h', 0, b'i', 0];
+        let d = decode(&le, EncodingChoice::Auto, false).unwrap();
+        assert_eq!(UTF_16LE, d.encoding);
+        assert_eq!("hi", d.text);
+
+        let be = [0xFE, 0xFF, 0, b'h', 0, b'i'];
+        let d = decode(&be, EncodingChoice::Auto, false).unwrap();
+        assert_eq!(UTF_16BE, d.encoding);
+        assert_eq!("hi", d.text);
+    }
+
+    #[test]
+    fn auto_strips_utf8_bom() {
+        let d = decode(b"\xEF\xBB\xBFabc", EncodingChoice::Auto, false).unwrap();
+        assert_eq!(UTF_8, d.encoding);
+        assert_eq!("abc", d.text);
+        assert!(d.bom);
+    }
+
+    #[test]
+    fn detect_guesses_cyrillic_vs_latin() {
+        let (cp1251, _, _) = WINDOWS_1251.encode("Привет мир");
+        assert_eq!(WINDOWS_1251, detect(&cp1251));
+
+        let (latin, _, _) = WINDOWS_1252.encode("café au lait");
+        assert_eq!(WINDOWS_1252, detect(&latin));
+    }
+
+    #[test]
+    fn fixed_encoding_reports_first_invalid_offset() {
+        let choice: EncodingChoice = "utf-8".parse().unwrap();
+        let err = decode(b"ab\xFFcd", choice, false).err().unwrap();
+
+        assert!(matches!(err, TextkitError::Decode { offset: 2, .. }));
+    }
+
+    #[test]
+    fn lossy_replaces_and_counts_invalid_sequences() {
+        let choice: EncodingChoice = "utf-8".parse().unwrap();
+        let d = decode(b"a\xFFb\xFEc", choice, true).unwrap();
+
+        assert_eq!("a\u{FFFD}b\u{FFFD}c", d.text);
+        assert_eq!(2, d.invalid_sequences);
+    }
+
+    #[test]
+    fn unknown_label_is_invalid_argument() {
+        let err = "klingon".parse::<EncodingChoice>().err().unwrap();
+        assert!(matches!(err, TextkitError::InvalidArgument(_)));
+    }
+}
@@ -4,6 +4,12 @@ use std::fmt;
 pub enum TextkitError {
     Io(std::io::Error),
     InvalidArgument(String),
+    Decode {
+        encoding: &'static str,
+        offset: usize,
+    },
+    Config(String),
+    Binary(String),
 }
 
 impl fmt::Display for TextkitError {
@@ -11,6 +17,14 @@ impl fmt::Display for TextkitError {
         match self {
             TextkitError::Io(err) => write!(f, "IO error: {err}"),
             TextkitError::InvalidArgument(msg) => write!(f, "Invalid argument: {msg}"),
+            TextkitError::Decode { encoding, offset } => {
+                write!(
+                    f,
+                    "Decode error: invalid {encoding} sequence at byte {offset}"
+                )
+            }
+            TextkitError::Config(msg) => write!(f, "Config error: {msg}"),
+            TextkitError::Binary(path) => write!(f, "Binary file {path} is not text"),
         }
     }
 }
@@ -1,36 +1,91 @@
 use crate::errors::TextkitError;
+use std::ops::Range;
 
-pub fn grep_lines(
-    text: &str,
+/// A line containing the pattern.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineMatch<'a> {
+    /// 1-based line number.
+    pub line_number: usize,
+    pub line: &'a str,
+    /// Byte ranges of every non-overlapping match in `line`, in order.
+    pub spans: Vec<Range<usize>>,
+}
+
+impl LineMatch<'_> {
+    /// 1-based byte column of the first match, as editors' quickfix lists expect.
+    pub fn column(&self) -> usize {
+        self.spans.first().map_or(1, |span| span.start + 1)
+    }
+}
+
+pub fn grep_matches<'a>(
+    text: &'a str,
     pattern: &str,
     ignore_case: bool,
-    line_numbers: bool,
-) -> Result<Vec<String>, TextkitError> {
-    let mut out: Vec<String> = Vec::new();
+) -> Result<Vec<LineMatch<'a>>, TextkitError> {
+    Ok(grep_iter(text, pattern, ignore_case).collect())
+}
+
+/// Lazily yields the matching lines of `text`, so callers can stream results
+/// without collecting them.
+pub fn grep_iter<'a>(
+    text: &'a str,
+    pattern: &str,
+    ignore_case: bool,
+) -> impl Iterator<Item = LineMatch<'a>> + use<'a> {
+    let needle = if ignore_case {
+        pattern.to_ascii_lowercase()
+    } else {
+        pattern.to_string()
+    };
 
-    let pattern_lower = pattern.to_ascii_lowercase();
-    for (i, line) in text.lines().enumerate() {
-        let matched = if ignore_case {
-            line.to_ascii_lowercase().contains(&pattern_lower)
+    text.lines().enumerate().filter_map(move |(i, line)| {
+        // ASCII lowercasing keeps byte offsets, so the spans are valid for `line` too
+        let spans = if ignore_case {
+            spans(&line.to_ascii_lowercase(), &needle)
         } else {
-            line.contains(pattern)
+            spans(line, &needle)
         };
 
-        if matched {
+        (!spans.is_empty()).then_some(LineMatch {
+            line_number: i + 1,
+            line,
+            spans,
+        })
+    })
+}
+
+fn spans(haystack: &str, needle: &str) -> Vec<Range<usize>> {
+    haystack
+        .match_indices(needle)
+        .map(|(start, m)| start..start + m.len())
+        .collect()
+}
+
+pub fn grep_lines(
+    text: &str,
+    pattern: &str,
+    ignore_case: bool,
+    line_numbers: bool,
+) -> Result<Vec<String>, TextkitError> {
+    let out = grep_matches(text, pattern, ignore_case)?
+        .into_iter()
+        .map(|m| {
             if line_numbers {
-                out.push(format!("{}:{}", i + 1, line));
+                format!("{}:{}", m.line_number, m.line)
             } else {
-                out.push(line.to_string());
+                m.line.to_string()
             }
-        }
-    }
+        })
+        .collect();
 
     Ok(out)
 }
 
 #[cfg(test)]
+#[allow(clippy::needless_borrow)]
 mod tests {
-    use super::grep_lines;
+    use super::{LineMatch, grep_iter, grep_lines, grep_matches};
 
     #[test]
     fn ignore_case_grep_lines_any_case() {
@@ -61,4 +116,35 @@ mod tests {
 
         assert_eq!(expected, result);
     }
+
+    #[test]
+    fn grep_matches_reports_every_span() {
+        let input = "alpha Alpha\nbeta ALPHA alpha\n";
+        let result = grep_matches(input, "alpha", true).unwrap();
+
+        let expected = vec![
+            LineMatch {
+                line_number: 1,
+                line: "alpha Alpha",
+                spans: vec![0..5, 6..11],
+            },
+            LineMatch {
+                line_number: 2,
+                line: "beta ALPHA alpha",
+                spans: vec![5..10, 11..16],
+            },
+        ];
+
+        assert_eq!(expected, result);
+        assert_eq!(6, result[1].column());
+    }
+
+    #[test]
+    fn grep_iter_is_lazy() {
+        let input = "x1\ny\nx2\nx3\n";
+        let mut iter = grep_iter(input, "x", false);
+
+        assert_eq!(Some(1), iter.next().map(|m| m.line_number));
+        assert_eq!(Some(3), iter.next().map(|m| m.line_number));
+    }
 }
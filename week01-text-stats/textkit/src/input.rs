@@ -0,0 +1,105 @@
+use crate::errors::TextkitError;
+use bzip2::read::MultiBzDecoder;
+use flate2::read::MultiGzDecoder;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    None,
+    Gzip,
+    Zstd,
+    Bzip2,
+}
+
+impl Compression {
+    /// Identifies the compression format from the leading magic bytes.
+    pub fn sniff(prefix: &[u8]) -> Self {
+        match prefix {
+            [0x1F, 0x8B, ..] => Compression::Gzip,
+            [0x28, 0xB5, 0x2F, 0xFD, ..] => Compression::Zstd,
+            [b'B', b'Z', b'h', b'1'..=b'9', ..] => Compression::Bzip2,
+            _ => Compression::None,
+        }
+    }
+}
+
+/// Opens `path` for streaming, transparently decompressing gzip, zstd and
+/// bzip2 content regardless of the file extension.
+pub fn open(path: impl AsRef<Path>) -> Result<Box<dyn BufRead>, TextkitError> {
+    decompress(File::open(path)?)
+}
+
+/// Wraps `reader` in the decompressor matching its magic bytes, or returns
+/// it buffered as-is when the content is not compressed.
+pub fn decompress<'a>(reader: impl Read + 'a) -> Result<Box<dyn BufRead + 'a>, TextkitError> {
+    let mut reader = BufReader::new(reader);
+
+    let out: Box<dyn BufRead + 'a> = match Compression::sniff(reader.fill_buf()?) {
+        Compression::None => Box::new(reader),
+        Compression::Gzip => Box::new(BufReader::new(MultiGzDecoder::new(reader))),
+        Compression::Zstd => Box::new(BufReader::new(zstd::Decoder::with_buffer(reader)?)),
+        Compression::Bzip2 => Box::new(BufReader::new(MultiBzDecoder::new(reader))),
+    };
+
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Compression, decompress};
+    use std::io::{Read, Write};
+
+    const TEXT: &str = "first line\nsecond line\n";
+
+    fn read_all(bytes: &[u8]) -> String {
+        let mut out = String::new();
+        decompress(bytes).unwrap().read_to_string(&mut out).unwrap();
+        out
+    }
+
+    #[test]
+    fn sniff_recognizes_magic_bytes() {
+        assert_eq!(Compression::Gzip, Compression::sniff(&[0x1F, 0x8B, 0x08]));
+        assert_eq!(
+            Compression::Zstd,
+            Compression::sniff(&[0x28, 0xB5, 0x2F, 0xFD])
+        );
+        assert_eq!(Compression::Bzip2, Compression::sniff(b"BZh91AY"));
+        assert_eq!(Compression::None, Compression::sniff(b"BZhello"));
+        assert_eq!(Compression::None, Compression::sniff(b""));
+    }
+
+    #[test]
+    fn plain_text_passes_through() {
+        assert_eq!(TEXT, read_all(TEXT.as_bytes()));
+    }
+
+    #[test]
+    fn gzip_roundtrip_including_concatenated_members() {
+        let mut bytes = Vec::new();
+        for part in ["first line\n", "second line\n"] {
+            let mut enc = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+            enc.write_all(part.as_bytes()).unwrap();
+            bytes.extend(enc.finish().unwrap());
+        }
+
+        assert_eq!(TEXT, read_all(&bytes));
+    }
+
+    #[test]
+    fn zstd_roundtrip() {
+        let bytes = zstd::encode_all(TEXT.as_bytes(), 0).unwrap();
+        assert_eq!(TEXT, read_all(&bytes));
+    }
+
+    #[test]
+    fn bzip2_roundtrip() {
+        let mut enc = bzip2::write::BzEncoder::new(Vec::new(), bzip2::Compression::default());
+        enc.write_all(TEXT.as_bytes()).unwrap();
+        let bytes = enc.finish().unwrap();
+
+        assert_eq!(TEXT, read_all(&bytes));
+    }
+}
@@ -1,4 +1,12 @@
+pub mod binary;
+pub mod color;
+pub mod config;
+pub mod encoding;
 pub mod errors;
 pub mod grep;
+pub mod input;
+pub mod normalize;
+pub mod split;
 pub mod stats;
+pub mod template;
 pub mod uniq;
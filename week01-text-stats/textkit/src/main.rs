@@ -1,13 +1,50 @@
-use clap::{Parser, Subcommand};
+use clap::{ArgGroup, CommandFactory, Parser, Subcommand};
+use clap_complete::Shell;
+use std::io::{self, IsTerminal, Read, Write};
+use std::path::PathBuf;
+use std::process::ExitCode;
+use textkit::binary::{BinaryFiles, is_binary};
+use textkit::color::{self, ColorChoice, highlight, paint};
+use textkit::config::{self, Config, GrepFlags, UniqFlags};
+use textkit::encoding::{Decoded, EncodingChoice, decode};
 use textkit::errors::TextkitError;
-use textkit::grep::grep_lines;
-use textkit::stats::analyze;
-use textkit::uniq::uniq_lines;
+use textkit::grep::grep_matches;
+use textkit::input;
+use textkit::normalize::{Indent, LineEnding, NormalizeOptions, UnicodeForm, normalize};
+use textkit::split::{SplitMode, parse_size, split_text};
+use textkit::stats::{analyze, column_values, distribution, histogram, line_lengths};
+use textkit::template::{GREP_FIELDS, Template, UNIQ_FIELDS};
+use textkit::uniq::{uniq_counts, uniq_lines};
+
+const BIN_NAME: &str = "textkit";
 
 #[derive(Parser, Debug)]
+#[command(name = BIN_NAME)]
 struct Args {
     #[command(subcommand)]
     command: Command,
+    #[command(flatten)]
+    global: GlobalArgs,
+}
+
+/// Flags accepted by every subcommand.
+#[derive(clap::Args, Debug)]
+struct GlobalArgs {
+    /// Input encoding: `auto` (default) or any WHATWG label (utf-8, utf-16le, windows-1251, latin1, ...).
+    #[arg(long, global = true, value_name = "ENCODING")]
+    encoding: Option<EncodingChoice>,
+    /// Replace invalid byte sequences with U+FFFD instead of failing.
+    #[arg(long, global = true, overrides_with = "no_lossy")]
+    lossy: bool,
+    /// Fail on invalid byte sequences, even if the config enables `lossy`.
+    #[arg(long, global = true)]
+    no_lossy: bool,
+    /// What to do with binary input: `binary` (default), `text`, `without-match` or `skip`.
+    #[arg(long, global = true, value_name = "MODE")]
+    binary_files: Option<BinaryFiles>,
+    /// Read settings from this file instead of looking up `textkit.toml`.
+    #[arg(long, global = true, value_name = "PATH")]
+    config: Option<PathBuf>,
 }
 
 #[derive(Subcommand, Debug)]
@@ -21,8 +58,17 @@ enum Command {
     Uniq {
         #[arg(value_name = "PATH")]
         path: String,
-        #[arg(long)]
+        #[arg(long, overrides_with = "no_all")]
         all: bool,
+        /// Only collapse consecutive duplicates, even if the config enables `all`.
+        #[arg(long)]
+        no_all: bool,
+        /// Apply a named preset from the `[uniq.presets]` config table.
+        #[arg(long, value_name = "NAME")]
+        preset: Option<String>,
+        /// Format each line with a template using `{count}` and `{text}`.
+        #[arg(long, value_name = "TEMPLATE")]
+        template: Option<String>,
     },
     /// Print lines that match the given pattern.
     Grep {
@@ -30,76 +76,625 @@ enum Command {
         pattern: String,
         #[arg(value_name = "PATH")]
         path: String,
-        #[arg(short = 'n', long = "line-number")]
+        #[arg(short = 'n', long = "line-number", overrides_with = "no_line_number")]
         line_number: bool,
-        #[arg(short = 'i', long = "ignore-case")]
+        /// Do not print line numbers, even if the config enables them.
+        #[arg(long = "no-line-number")]
+        no_line_number: bool,
+        #[arg(short = 'i', long = "ignore-case", overrides_with = "no_ignore_case")]
         ignore_case: bool,
+        /// Match case-sensitively, even if the config enables `ignore_case`.
+        #[arg(long = "no-ignore-case")]
+        no_ignore_case: bool,
+        /// Apply a named preset from the `[grep.presets]` config table.
+        #[arg(long, value_name = "NAME")]
+        preset: Option<String>,
+        /// Format each match with a template using `{file}`, `{line}`, `{col}` and `{text}`.
+        #[arg(long, value_name = "TEMPLATE")]
+        template: Option<String>,
+        /// Highlight matches, line numbers and file names: `auto`, `always` or `never`.
+        #[arg(
+            long,
+            value_name = "WHEN",
+            default_value = "auto",
+            require_equals = true,
+            num_args = 0..=1,
+            default_missing_value = "always"
+        )]
+        color: ColorChoice,
+    },
+    /// Normalize line endings, whitespace and Unicode form; output is UTF-8.
+    Normalize {
+        #[arg(value_name = "PATH")]
+        path: String,
+        /// Convert every line ending to `lf` or `crlf`.
+        #[arg(long, value_name = "STYLE")]
+        line_ending: Option<LineEnding>,
+        /// Remove trailing whitespace from every line.
+        #[arg(long)]
+        strip_trailing: bool,
+        /// Expand tabs to spaces with the given tab width.
+        #[arg(long, value_name = "WIDTH", conflicts_with = "spaces_to_tabs")]
+        tabs_to_spaces: Option<usize>,
+        /// Replace leading runs of WIDTH spaces with tabs.
+        #[arg(long, value_name = "WIDTH")]
+        spaces_to_tabs: Option<usize>,
+        /// Apply Unicode normalization form `nfc` or `nfkc`.
+        #[arg(long, value_name = "FORM")]
+        unicode: Option<UnicodeForm>,
+        /// Drop the byte order mark instead of keeping it.
+        #[arg(long)]
+        strip_bom: bool,
+        /// Write the result to a file instead of stdout.
+        #[arg(short = 'o', long, value_name = "PATH")]
+        output: Option<String>,
+    },
+    /// Report the distribution of line lengths, or of a numeric column.
+    Histogram {
+        #[arg(value_name = "PATH")]
+        path: String,
+        /// Use the numeric values of this 1-based field instead of line lengths.
+        #[arg(short = 'f', long, value_name = "N")]
+        field: Option<usize>,
+        /// Field delimiter; fields are split on whitespace by default.
+        #[arg(short = 'd', long, value_name = "CHAR", requires = "field")]
+        delimiter: Option<char>,
+        /// Number of bars in the chart.
+        #[arg(long, value_name = "N", default_value_t = 10)]
+        buckets: usize,
+        /// Width of the longest bar in characters.
+        #[arg(long, value_name = "N", default_value_t = 40)]
+        width: usize,
+    },
+    /// Split a file into numbered parts by lines, bytes or a pattern.
+    #[command(group(ArgGroup::new("mode").required(true)))]
+    Split {
+        #[arg(value_name = "PATH")]
+        path: String,
+        /// Put at most N lines in each part.
+        #[arg(short = 'l', long, value_name = "N", group = "mode")]
+        lines: Option<usize>,
+        /// Put at most SIZE bytes (K, M, G suffixes) in each part; lines are never cut.
+        #[arg(short = 'b', long, value_name = "SIZE", group = "mode", value_parser = parse_size)]
+        bytes: Option<usize>,
+        /// Start a new part at every line containing PATTERN, like csplit.
+        #[arg(short = 'p', long, value_name = "PATTERN", group = "mode")]
+        pattern: Option<String>,
+        /// Output file prefix; parts are named PREFIX00, PREFIX01, ...
+        #[arg(long, value_name = "PREFIX", default_value = "x")]
+        prefix: String,
+        /// Manifest file listing every part and its line range [default: PREFIXmanifest.tsv]
+        #[arg(long, value_name = "PATH")]
+        manifest: Option<PathBuf>,
+    },
+    /// Print a shell completion script to stdout.
+    Completions {
+        #[arg(value_name = "SHELL")]
+        shell: Shell,
+    },
+    /// Inspect the configuration file.
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+    /// Generate roff man pages for textkit and every subcommand.
+    Man {
+        /// Write one `.1` file per page into this directory instead of stdout.
+        #[arg(long, value_name = "DIR")]
+        out_dir: Option<PathBuf>,
     },
 }
 
-fn main() {
-    let args = Args::parse();
+#[derive(Subcommand, Debug)]
+enum ConfigAction {
+    /// Print the config file in use and the effective settings.
+    Show,
+}
 
-    let path = match &args.command {
-        Command::Stats { path } => path,
-        Command::Uniq { path, .. } => path,
-        Command::Grep { path, .. } => path,
-    };
+fn main() -> ExitCode {
+    let args = Args::parse();
 
-    let text = match read_text(path) {
-        Ok(t) => t,
+    match run(args) {
+        Ok(()) => ExitCode::SUCCESS,
         Err(e) => {
             eprintln!("Error: {e}");
-            return;
+            ExitCode::FAILURE
         }
-    };
+    }
+}
+
+fn run(args: Args) -> Result<(), TextkitError> {
+    // loaded by each command that uses it, so a broken `textkit.toml` does
+    // not break `completions` or `man`
+    let settings = || Settings::load(&args.global);
 
     match args.command {
-        Command::Stats { .. } => {
-            let s = match analyze(&text) {
-                Ok(s) => s,
-                Err(e) => {
-                    eprintln!("Error: {e}");
-                    return;
-                }
+        Command::Stats { path } => {
+            let input = settings()?.input;
+            let Some(decoded) = read_text(&path, &input)? else {
+                return Ok(());
             };
+            let s = analyze(&decoded.text)?;
             println!(
                 "lines: {}\nwords: {}\nchars: {}\nbytes: {}",
                 s.lines, s.words, s.chars, s.bytes
             );
+            println!(
+                "encoding: {}\ninvalid sequences: {}",
+                decoded.encoding.name(),
+                decoded.invalid_sequences
+            );
+            println!(
+                "line endings: {}\nmixed endings: {}",
+                s.line_endings.style(),
+                s.line_endings.mixed()
+            );
         }
-        Command::Uniq { all, .. } => {
-            let lines = match uniq_lines(&text, all) {
-                Ok(lines) => lines,
-                Err(e) => {
-                    eprintln!("Error: {e}");
-                    return;
-                }
+        Command::Uniq {
+            path,
+            all,
+            no_all,
+            preset,
+            template,
+        } => {
+            let template = template
+                .map(|t| Template::parse(&t, UNIQ_FIELDS))
+                .transpose()?;
+            let Settings {
+                config: cfg, input, ..
+            } = settings()?;
+            let settings = cfg.uniq(
+                UniqFlags {
+                    all: flag(all, no_all),
+                },
+                preset.as_deref(),
+            )?;
+            let Some(decoded) = read_text(&path, &input)? else {
+                return Ok(());
             };
-            for line in lines {
+            if let Some(template) = template {
+                for line in uniq_counts(&decoded.text, settings.all)? {
+                    println!("{}", template.render(&[&line.count, &line.text]));
+                }
+                return Ok(());
+            }
+            for line in uniq_lines(&decoded.text, settings.all)? {
                 println!("{line}");
             }
         }
         Command::Grep {
             pattern,
-            ignore_case,
+            path,
             line_number,
-            ..
+            no_line_number,
+            ignore_case,
+            no_ignore_case,
+            preset,
+            template,
+            color,
         } => {
-            let lines = match grep_lines(&text, &pattern, ignore_case, line_number) {
-                Ok(lines) => lines,
-                Err(e) => {
-                    eprintln!("Error: {e}");
-                    return;
+            let template = template
+                .map(|t| Template::parse(&t, GREP_FIELDS))
+                .transpose()?;
+            let Settings {
+                config: cfg, input, ..
+            } = settings()?;
+            let settings = cfg.grep(
+                GrepFlags {
+                    line_number: flag(line_number, no_line_number),
+                    ignore_case: flag(ignore_case, no_ignore_case),
+                },
+                preset.as_deref(),
+            )?;
+            let decoded = match read_input(&path, &input)? {
+                Input::Text(decoded) => decoded,
+                Input::Binary(bytes) => {
+                    let decoded = decode(&bytes, input.encoding, true)?;
+                    let matches = grep_matches(&decoded.text, &pattern, settings.ignore_case)?;
+                    if !matches.is_empty() {
+                        println!("Binary file {path} matches");
+                    }
+                    return Ok(());
                 }
+                Input::Skipped => return Ok(()),
             };
-            for line in lines {
-                println!("{line}");
+            let color = color.enabled(io::stdout().is_terminal(), no_color());
+            let separator = if color {
+                paint(":", color::SEPARATOR)
+            } else {
+                ":".to_string()
+            };
+            for m in grep_matches(&decoded.text, &pattern, settings.ignore_case)? {
+                let (file, number, text) = if color {
+                    (
+                        paint(&path, color::FILE_NAME),
+                        paint(&m.line_number.to_string(), color::LINE_NUMBER),
+                        highlight(m.line, &m.spans),
+                    )
+                } else {
+                    (path.clone(), m.line_number.to_string(), m.line.to_string())
+                };
+                match &template {
+                    Some(t) => println!("{}", t.render(&[&file, &number, &m.column(), &text])),
+                    None if settings.line_number => println!("{number}{separator}{text}"),
+                    None => println!("{text}"),
+                }
+            }
+        }
+        Command::Normalize {
+            path,
+            line_ending,
+            strip_trailing,
+            tabs_to_spaces,
+            spaces_to_tabs,
+            unicode,
+            strip_bom,
+            output,
+        } => {
+            let input = settings()?.input;
+            let Some(decoded) = read_text(&path, &input)? else {
+                return Ok(());
+            };
+            let opts = NormalizeOptions {
+                line_ending,
+                strip_trailing_whitespace: strip_trailing,
+                indent: tabs_to_spaces
+                    .map(Indent::TabsToSpaces)
+                    .or(spaces_to_tabs.map(Indent::SpacesToTabs)),
+                unicode,
+                strip_bom,
+            };
+            // decoding drops the BOM; hand it back so `normalize` decides
+            let text = if decoded.bom {
+                format!("\u{FEFF}{}", decoded.text)
+            } else {
+                decoded.text
+            };
+            let out = normalize(&text, &opts)?;
+            write_output(output.as_deref(), &out)?;
+        }
+        Command::Histogram {
+            path,
+            field,
+            delimiter,
+            buckets,
+            width,
+        } => {
+            let input = settings()?.input;
+            let Some(decoded) = read_text(&path, &input)? else {
+                return Ok(());
+            };
+            let values = match field {
+                Some(field) => {
+                    let column = column_values(&decoded.text, field, delimiter)?;
+                    println!("skipped: {}", column.skipped);
+                    column.values
+                }
+                None => line_lengths(&decoded.text),
+            };
+            print_histogram(&values, buckets, width)?;
+        }
+        Command::Split {
+            path,
+            lines,
+            bytes,
+            pattern,
+            prefix,
+            manifest,
+        } => {
+            let mode = lines
+                .map(SplitMode::Lines)
+                .or(bytes.map(SplitMode::Bytes))
+                .or(pattern.map(SplitMode::Pattern))
+                .expect("clap's `mode` group requires exactly one of the flags");
+            let input = settings()?.input;
+            let Some(decoded) = read_text(&path, &input)? else {
+                return Ok(());
+            };
+            let chunks = split_text(&decoded.text, &mode)?;
+            let digits = chunks.len().saturating_sub(1).to_string().len().max(2);
+
+            let mut listing = String::from("file\tfirst_line\tlast_line\tbytes\n");
+            for (i, chunk) in chunks.iter().enumerate() {
+                let name = format!("{prefix}{i:0digits$}");
+                std::fs::write(&name, chunk.text)?;
+                listing.push_str(&format!(
+                    "{name}\t{}\t{}\t{}\n",
+                    chunk.first_line,
+                    chunk.last_line,
+                    chunk.text.len()
+                ));
+            }
+
+            let manifest =
+                manifest.unwrap_or_else(|| PathBuf::from(format!("{prefix}manifest.tsv")));
+            std::fs::write(manifest, listing)?;
+        }
+        Command::Completions { shell } => {
+            clap_complete::generate(shell, &mut Args::command(), BIN_NAME, &mut io::stdout());
+        }
+        Command::Config {
+            action: ConfigAction::Show,
+        } => {
+            let Settings { path, config, .. } = settings()?;
+            match &path {
+                Some(path) => println!("# config file: {}", path.display()),
+                None => println!("# config file: none (built-in defaults)"),
+            }
+            print!("{}", config.effective().to_toml()?);
+        }
+        Command::Man { out_dir } => {
+            for (name, page) in man_pages()? {
+                match &out_dir {
+                    Some(dir) => std::fs::write(dir.join(name), page)?,
+                    None => io::stdout().write_all(&page)?,
+                }
             }
         }
     }
+
+    Ok(())
 }
 
-fn read_text(path: &str) -> Result<String, TextkitError> {
-    Ok(std::fs::read_to_string(path)?)
+fn print_histogram(values: &[f64], buckets: usize, width: usize) -> Result<(), TextkitError> {
+    let bars = histogram(values, buckets)?;
+    let Some(d) = distribution(values) else {
+        println!("count: 0");
+        return Ok(());
+    };
+
+    println!(
+        "count: {}\nmin: {}\nmax: {}\nmean: {:.2}",
+        d.count, d.min, d.max, d.mean
+    );
+    println!(
+        "p50: {}\np90: {}\np95: {}\np99: {}",
+        d.p50, d.p90, d.p95, d.p99
+    );
+
+    let peak = bars.iter().map(|b| b.count).max().unwrap_or(0).max(1);
+    let labels: Vec<String> = bars
+        .iter()
+        .map(|b| format!("[{:.2}, {:.2}]", b.lower, b.upper))
+        .collect();
+    let label_width = labels.iter().map(String::len).max().unwrap_or(0);
+
+    for (bar, label) in bars.iter().zip(&labels) {
+        let len = (bar.count * width).div_ceil(peak);
+        println!("{label:<label_width$} | {} {}", "#".repeat(len), bar.count);
+    }
+
+    Ok(())
+}
+
+/// Maps a `--flag` / `--no-flag` pair to "set", "unset" or "not given".
+fn flag(yes: bool, no: bool) -> Option<bool> {
+    match (yes, no) {
+        (true, _) => Some(true),
+        (false, true) => Some(false),
+        (false, false) => None,
+    }
+}
+
+fn load_config(explicit: Option<PathBuf>) -> Result<(Option<PathBuf>, Config), TextkitError> {
+    let path = match explicit {
+        Some(path) => Some(path),
+        None => config::find(
+            &std::env::current_dir()?,
+            std::env::var_os("XDG_CONFIG_HOME")
+                .map(PathBuf::from)
+                .as_deref(),
+            std::env::var_os("HOME").map(PathBuf::from).as_deref(),
+        ),
+    };
+
+    match path {
+        Some(path) => {
+            let cfg = Config::load(&path)?;
+            Ok((Some(path), cfg))
+        }
+        None => Ok((None, Config::default())),
+    }
+}
+
+/// Renders `textkit.1` plus a `textkit-<subcommand>.1` page for every subcommand.
+fn man_pages() -> Result<Vec<(String, Vec<u8>)>, TextkitError> {
+    let mut cmd = Args::command();
+    cmd.build();
+
+    let mut pages = Vec::new();
+    let mut page = Vec::new();
+    clap_mangen::Man::new(cmd.clone()).render(&mut page)?;
+    pages.push((format!("{BIN_NAME}.1"), page));
+
+    for sub in cmd.get_subcommands().filter(|s| s.get_name() != "help") {
+        // `build()` gives subcommands a display name of `textkit-<name>`
+        let name = sub.get_display_name().unwrap_or(sub.get_name());
+        let mut page = Vec::new();
+        clap_mangen::Man::new(sub.clone()).render(&mut page)?;
+        pages.push((format!("{name}.1"), page));
+    }
+
+    Ok(pages)
+}
+
+fn write_output(path: Option<&str>, text: &str) -> Result<(), TextkitError> {
+    match path {
+        Some(path) => std::fs::write(path, text)?,
+        None => io::stdout().write_all(text.as_bytes())?,
+    }
+    Ok(())
+}
+
+/// The config file in use and the input options resolved from it and the
+/// global flags.
+struct Settings {
+    path: Option<PathBuf>,
+    config: Config,
+    input: InputOptions,
+}
+
+impl Settings {
+    fn load(args: &GlobalArgs) -> Result<Self, TextkitError> {
+        let (path, config) = load_config(args.config.clone())?;
+        let input = InputOptions {
+            encoding: config.encoding(args.encoding)?,
+            lossy: config.lossy(flag(args.lossy, args.no_lossy)),
+            binary_files: config.binary_files(args.binary_files),
+        };
+        Ok(Settings {
+            path,
+            config,
+            input,
+        })
+    }
+}
+
+struct InputOptions {
+    encoding: EncodingChoice,
+    lossy: bool,
+    binary_files: BinaryFiles,
+}
+
+/// Whether `NO_COLOR` is set to a non-empty value (see no-color.org).
+fn no_color() -> bool {
+    std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty())
+}
+
+/// Reads the whole of `path`, decompressed: encoding and binary detection
+/// and every command work on the complete text.
+fn read_bytes(path: &str) -> Result<Vec<u8>, TextkitError> {
+    let mut bytes = Vec::new();
+    input::open(path)?.read_to_end(&mut bytes)?;
+    Ok(bytes)
+}
+
+enum Input {
+    Text(Decoded),
+    /// Binary input under `--binary-files=binary`.
+    Binary(Vec<u8>),
+    Skipped,
+}
+
+fn read_input(path: &str, opts: &InputOptions) -> Result<Input, TextkitError> {
+    let bytes = read_bytes(path)?;
+
+    if opts.binary_files == BinaryFiles::Text || !is_binary(&bytes, opts.encoding) {
+        return decode(&bytes, opts.encoding, opts.lossy).map(Input::Text);
+    }
+
+    match opts.binary_files {
+        BinaryFiles::Binary => Ok(Input::Binary(bytes)),
+        BinaryFiles::Skip => {
+            eprintln!("textkit: skipping binary file {path}");
+            Ok(Input::Skipped)
+        }
+        BinaryFiles::WithoutMatch | BinaryFiles::Text => Ok(Input::Skipped),
+    }
+}
+
+/// Reads and decodes `path` for commands that only work on text; `None`
+/// means binary input was skipped.
+fn read_text(path: &str, opts: &InputOptions) -> Result<Option<Decoded>, TextkitError> {
+    match read_input(path, opts)? {
+        Input::Text(decoded) => Ok(Some(decoded)),
+        Input::Binary(_) => Err(TextkitError::Binary(path.to_string())),
+        Input::Skipped => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Args, BIN_NAME, man_pages};
+    use clap::CommandFactory;
+    use clap_complete::Shell;
+
+    /// Every `--long` and `-s` flag of every subcommand, as written on the command line.
+    fn all_flags() -> Vec<(String, String)> {
+        let mut cmd = Args::command();
+        cmd.build();
+
+        let mut flags = Vec::new();
+        for sub in cmd.get_subcommands().filter(|s| s.get_name() != "help") {
+            for arg in sub.get_arguments() {
+                if let Some(long) = arg.get_long() {
+                    flags.push((sub.get_name().to_string(), format!("--{long}")));
+                }
+                if let Some(short) = arg.get_short() {
+                    flags.push((sub.get_name().to_string(), format!("-{short}")));
+                }
+            }
+        }
+        flags
+    }
+
+    /// Whether `script` offers `flag` the way the generator for `shell` spells
+    /// it, so that e.g. `-n` is not found inside fish's `complete -n`.
+    fn offers(shell: Shell, script: &str, flag: &str) -> bool {
+        let tokens: Vec<&str> = script
+            .split(|c: char| c.is_whitespace() || c == '"')
+            .collect();
+        match shell {
+            // `opts="-n -i --line-number ..."`
+            Shell::Bash => tokens.contains(&flag),
+            // `'-n[help]'`, `'--config=[help]:PATH:_files'`, `'-l+[help]'`
+            Shell::Zsh => ["[", "=[", "+["]
+                .iter()
+                .any(|end| script.contains(&format!("{flag}{end}"))),
+            // `complete ... -s n -l line-number`
+            Shell::Fish => {
+                let (kind, name) = match flag.strip_prefix("--") {
+                    Some(long) => ("-l", long),
+                    None => ("-s", &flag[1..]),
+                };
+                tokens.windows(2).any(|w| w == [kind, name])
+            }
+            // `cand -n 'help'`
+            Shell::Elvish => script.contains(&format!("cand {flag} ")),
+            // `[CompletionResult]::new('-n', '-n', ...)`
+            Shell::PowerShell => script.contains(&format!("::new('{flag}', '{flag}'")),
+            _ => unreachable!("not generated in these tests"),
+        }
+    }
+
+    #[test]
+    fn completions_mention_every_flag() {
+        for shell in [
+            Shell::Bash,
+            Shell::Zsh,
+            Shell::Fish,
+            Shell::Elvish,
+            Shell::PowerShell,
+        ] {
+            let mut out = Vec::new();
+            clap_complete::generate(shell, &mut Args::command(), BIN_NAME, &mut out);
+            let script = String::from_utf8(out).unwrap();
+
+            for (sub, flag) in all_flags() {
+                assert!(
+                    offers(shell, &script, &flag),
+                    "{shell} completions miss {sub} {flag}"
+                );
+            }
+            assert!(!offers(shell, &script, "-y"), "{shell} offers -y");
+        }
+    }
+
+    #[test]
+    fn man_pages_cover_every_subcommand_and_flag() {
+        let pages = man_pages().unwrap();
+
+        for (sub, flag) in all_flags() {
+            let name = format!("{BIN_NAME}-{sub}.1");
+            let (_, page) = pages
+                .iter()
+                .find(|(n, _)| *n == name)
+                .unwrap_or_else(|| panic!("missing man page {name}"));
+            let page = String::from_utf8_lossy(page);
+
+            // roff escapes every dash in option names
+            let escaped = flag.replace('-', "\\-");
+            assert!(page.contains(&escaped), "{name} misses {flag}");
+        }
+    }
 }
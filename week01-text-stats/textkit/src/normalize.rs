@@ -0,0 +1,233 @@
+use crate::errors::TextkitError;
+use std::str::FromStr;
+use text_stats::lines::LinesWithEndings;
+use unicode_normalization::UnicodeNormalization;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineEnding {
+    Lf,
+    Crlf,
+}
+
+impl LineEnding {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::Crlf => "\r\n",
+        }
+    }
+}
+
+impl FromStr for LineEnding {
+    type Err = TextkitError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "lf" => Ok(LineEnding::Lf),
+            "crlf" => Ok(LineEnding::Crlf),
+            _ => Err(TextkitError::InvalidArgument(format!(
+                "unknown line ending: {s} (expected lf or crlf)"
+            ))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnicodeForm {
+    Nfc,
+    Nfkc,
+}
+
+impl FromStr for UnicodeForm {
+    type Err = TextkitError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "nfc" => Ok(UnicodeForm::Nfc),
+            "nfkc" => Ok(UnicodeForm::Nfkc),
+            _ => Err(TextkitError::InvalidArgument(format!(
+                "unknown unicode form: {s} (expected nfc or nfkc)"
+            ))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Indent {
+    /// Expand every tab to the next multiple of the given width.
+    TabsToSpaces(usize),
+    /// Turn each run of the given number of leading spaces into a tab.
+    SpacesToTabs(usize),
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct NormalizeOptions {
+    /// Target line ending; `None` keeps each line's original ending.
+    pub line_ending: Option<LineEnding>,
+    pub strip_trailing_whitespace: bool,
+    pub indent: Option<Indent>,
+    pub unicode: Option<UnicodeForm>,
+    pub strip_bom: bool,
+}
+
+pub fn normalize(text: &str, opts: &NormalizeOptions) -> Result<String, TextkitError> {
+    if let Some(Indent::TabsToSpaces(0) | Indent::SpacesToTabs(0)) = opts.indent {
+        return Err(TextkitError::InvalidArgument(
+            "tab width must be greater than zero".to_string(),
+        ));
+    }
+
+    let text = if opts.strip_bom {
+        text.strip_prefix('\u{FEFF}').unwrap_or(text)
+    } else {
+        text
+    };
+
+    let text: String = match opts.unicode {
+        Some(UnicodeForm::Nfc) => text.nfc().collect(),
+        Some(UnicodeForm::Nfkc) => text.nfkc().collect(),
+        None => text.to_string(),
+    };
+
+    let mut out = String::with_capacity(text.len());
+    for (line, ending) in LinesWithEndings::new(&text) {
+        let line = match opts.indent {
+            Some(Indent::TabsToSpaces(width)) => expand_tabs(line, width),
+            Some(Indent::SpacesToTabs(width)) => unexpand_leading(line, width),
+            None => line.to_string(),
+        };
+
+        if opts.strip_trailing_whitespace {
+            out.push_str(line.trim_end());
+        } else {
+            out.push_str(&line);
+        }
+
+        match (ending.is_empty(), opts.line_ending) {
+            (true, _) => {}
+            (false, Some(target)) => out.push_str(target.as_str()),
+            (false, None) => out.push_str(ending),
+        }
+    }
+
+    Ok(out)
+}
+
+fn expand_tabs(line: &str, width: usize) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut col = 0;
+    for c in line.chars() {
+        if c == '\t' {
+            let pad = width - col % width;
+            out.extend(std::iter::repeat_n(' ', pad));
+            col += pad;
+        } else {
+            out.push(c);
+            col += 1;
+        }
+    }
+    out
+}
+
+fn unexpand_leading(line: &str, width: usize) -> String {
+    let indent_len = line.len() - line.trim_start_matches(' ').len();
+    let tabs = indent_len / width;
+    let mut out = "\t".repeat(tabs);
+    out.push_str(&line[tabs * width..]);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Indent, LineEnding, NormalizeOptions, UnicodeForm, normalize};
+    use crate::errors::TextkitError;
+
+    #[test]
+    fn default_options_keep_text_unchanged() {
+        let input = "a\r\nb\nc\rd";
+        let out = normalize(input, &NormalizeOptions::default()).unwrap();
+        assert_eq!(input, out);
+    }
+
+    #[test]
+    fn converts_all_endings_to_lf_and_back_to_crlf() {
+        let lf = NormalizeOptions {
+            line_ending: Some(LineEnding::Lf),
+            ..NormalizeOptions::default()
+        };
+        assert_eq!("a\nb\nc\nd", normalize("a\r\nb\nc\rd", &lf).unwrap());
+
+        let crlf = NormalizeOptions {
+            line_ending: Some(LineEnding::Crlf),
+            ..NormalizeOptions::default()
+        };
+        assert_eq!("a\r\nb\r\n", normalize("a\nb\r\n", &crlf).unwrap());
+    }
+
+    #[test]
+    fn strips_trailing_whitespace_but_keeps_endings() {
+        let opts = NormalizeOptions {
+            strip_trailing_whitespace: true,
+            ..NormalizeOptions::default()
+        };
+        assert_eq!("a\r\n  b\n", normalize("a \t\r\n  b  \n", &opts).unwrap());
+    }
+
+    #[test]
+    fn expands_tabs_to_tab_stops() {
+        let opts = NormalizeOptions {
+            indent: Some(Indent::TabsToSpaces(4)),
+            ..NormalizeOptions::default()
+        };
+        assert_eq!("    a   b\n", normalize("\ta\tb\n", &opts).unwrap());
+    }
+
+    #[test]
+    fn converts_leading_spaces_to_tabs_only() {
+        let opts = NormalizeOptions {
+            indent: Some(Indent::SpacesToTabs(4)),
+            ..NormalizeOptions::default()
+        };
+        assert_eq!(
+            "\t\t  a    b\n",
+            normalize("          a    b\n", &opts).unwrap()
+        );
+    }
+
+    #[test]
+    fn zero_tab_width_is_invalid_argument() {
+        let opts = NormalizeOptions {
+            indent: Some(Indent::TabsToSpaces(0)),
+            ..NormalizeOptions::default()
+        };
+        let err = normalize("a", &opts).err().unwrap();
+        assert!(matches!(err, TextkitError::InvalidArgument(_)));
+    }
+
+    #[test]
+    fn unicode_nfc_and_nfkc() {
+        // "e" + combining acute accent composes to "é"; the "ﬁ" ligature only folds under NFKC.
+        let input = "e\u{0301} \u{FB01}";
+
+        let nfc = NormalizeOptions {
+            unicode: Some(UnicodeForm::Nfc),
+            ..NormalizeOptions::default()
+        };
+        assert_eq!("\u{E9} \u{FB01}", normalize(input, &nfc).unwrap());
+
+        let nfkc = NormalizeOptions {
+            unicode: Some(UnicodeForm::Nfkc),
+            ..NormalizeOptions::default()
+        };
+        assert_eq!("\u{E9} fi", normalize(input, &nfkc).unwrap());
+    }
+
+    #[test]
+    fn strips_leading_bom() {
+        let opts = NormalizeOptions {
+            strip_bom: true,
+            ..NormalizeOptions::default()
+        };
+        assert_eq!("abc", normalize("\u{FEFF}abc", &opts).unwrap());
+    }
+}
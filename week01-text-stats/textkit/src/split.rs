@@ -0,0 +1,183 @@
+use crate::errors::TextkitError;
+use text_stats::lines::LinesWithEndings;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SplitMode {
+    /// At most this many lines per chunk.
+    Lines(usize),
+    /// At most this many bytes per chunk, cut only at line boundaries.
+    Bytes(usize),
+    /// Start a new chunk at every line containing the pattern (csplit-style).
+    Pattern(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Chunk<'a> {
+    /// The chunk's lines, including their line endings.
+    pub text: &'a str,
+    /// 1-based number of the first line.
+    pub first_line: usize,
+    /// 1-based number of the last line (inclusive).
+    pub last_line: usize,
+}
+
+/// Splits `text` into consecutive chunks that together reproduce it exactly.
+///
+/// Chunks always end on a line boundary, so byte-size splitting can never cut
+/// a UTF-8 character in half. A single line longer than the byte limit
+/// becomes a chunk of its own.
+pub fn split_text<'a>(text: &'a str, mode: &SplitMode) -> Result<Vec<Chunk<'a>>, TextkitError> {
+    match mode {
+        SplitMode::Lines(0) => Err(TextkitError::InvalidArgument(
+            "line count must be greater than zero".to_string(),
+        )),
+        SplitMode::Bytes(0) => Err(TextkitError::InvalidArgument(
+            "byte size must be greater than zero".to_string(),
+        )),
+        SplitMode::Pattern(p) if p.is_empty() => Err(TextkitError::InvalidArgument(
+            "pattern must not be empty".to_string(),
+        )),
+        SplitMode::Lines(n) => Ok(split_by(text, |_, lines, _| lines == *n)),
+        SplitMode::Bytes(n) => Ok(split_by(text, |line, _, bytes| bytes + line.len() > *n)),
+        SplitMode::Pattern(p) => Ok(split_by(text, |line, _, _| line.contains(p.as_str()))),
+    }
+}
+
+/// Walks the lines of `text`, ended by `\r\n`, `\n` or a lone `\r`, closing
+/// the current chunk before a line when `starts_new(line, lines_so_far,
+/// bytes_so_far)` says so. Empty chunks are never produced.
+fn split_by<'a>(text: &'a str, starts_new: impl Fn(&str, usize, usize) -> bool) -> Vec<Chunk<'a>> {
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    let mut first_line = 1;
+    let mut lines = 0;
+    let mut offset = 0;
+
+    for (line, ending) in LinesWithEndings::new(text) {
+        let line = &text[offset..offset + line.len() + ending.len()];
+        if lines > 0 && starts_new(line, lines, offset - start) {
+            chunks.push(Chunk {
+                text: &text[start..offset],
+                first_line,
+                last_line: first_line + lines - 1,
+            });
+            start = offset;
+            first_line += lines;
+            lines = 0;
+        }
+        offset += line.len();
+        lines += 1;
+    }
+
+    if lines > 0 {
+        chunks.push(Chunk {
+            text: &text[start..],
+            first_line,
+            last_line: first_line + lines - 1,
+        });
+    }
+
+    chunks
+}
+
+/// Parses a byte size with an optional `K`, `M` or `G` suffix (powers of 1024).
+pub fn parse_size(s: &str) -> Result<usize, TextkitError> {
+    let s = s.trim();
+    let (digits, multiplier) = match s.char_indices().last() {
+        Some((i, 'k' | 'K')) => (&s[..i], 1 << 10),
+        Some((i, 'm' | 'M')) => (&s[..i], 1 << 20),
+        Some((i, 'g' | 'G')) => (&s[..i], 1 << 30),
+        _ => (s, 1),
+    };
+
+    digits
+        .parse::<usize>()
+        .ok()
+        .and_then(|n| n.checked_mul(multiplier))
+        .ok_or_else(|| TextkitError::InvalidArgument(format!("invalid size: {s}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Chunk, SplitMode, parse_size, split_text};
+    use crate::errors::TextkitError;
+
+    fn ranges(chunks: &[Chunk<'_>]) -> Vec<(usize, usize)> {
+        chunks.iter().map(|c| (c.first_line, c.last_line)).collect()
+    }
+
+    #[test]
+    fn split_by_line_count() {
+        let chunks = split_text("a\nb\nc\nd\ne", &SplitMode::Lines(2)).unwrap();
+
+        assert_eq!(vec![(1, 2), (3, 4), (5, 5)], ranges(&chunks));
+        assert_eq!("a\nb\n", chunks[0].text);
+        assert_eq!("e", chunks[2].text);
+    }
+
+    #[test]
+    fn split_by_bytes_keeps_lines_and_chars_whole() {
+        // each "ééé\n" line is 7 bytes
+        let text = "ééé\nééé\nééé\n";
+        let chunks = split_text(text, &SplitMode::Bytes(15)).unwrap();
+
+        assert_eq!(vec![(1, 2), (3, 3)], ranges(&chunks));
+        assert_eq!(text, chunks.iter().map(|c| c.text).collect::<String>());
+    }
+
+    #[test]
+    fn oversized_line_gets_its_own_chunk() {
+        let chunks = split_text("a\nlong line\nb\n", &SplitMode::Bytes(4)).unwrap();
+        assert_eq!(vec![(1, 1), (2, 2), (3, 3)], ranges(&chunks));
+    }
+
+    #[test]
+    fn split_at_pattern_like_csplit() {
+        let text = "preamble\n== one\nx\n== two\ny\n";
+        let chunks = split_text(text, &SplitMode::Pattern("==".to_string())).unwrap();
+
+        assert_eq!(vec![(1, 1), (2, 3), (4, 5)], ranges(&chunks));
+        assert_eq!("== two\ny\n", chunks[2].text);
+    }
+
+    #[test]
+    fn pattern_on_first_line_does_not_create_empty_chunk() {
+        let chunks = split_text("# a\n1\n# b\n", &SplitMode::Pattern("#".to_string())).unwrap();
+        assert_eq!(vec![(1, 2), (3, 3)], ranges(&chunks));
+    }
+
+    #[test]
+    fn lone_carriage_return_ends_a_line() {
+        let text = "a\rb\r\nc\rd";
+        let chunks = split_text(text, &SplitMode::Lines(1)).unwrap();
+
+        assert_eq!(vec![(1, 1), (2, 2), (3, 3), (4, 4)], ranges(&chunks));
+        assert_eq!(
+            vec!["a\r", "b\r\n", "c\r", "d"],
+            chunks.iter().map(|c| c.text).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn empty_text_has_no_chunks() {
+        assert!(split_text("", &SplitMode::Lines(10)).unwrap().is_empty());
+    }
+
+    #[test]
+    fn zero_sizes_are_invalid() {
+        let err = split_text("a", &SplitMode::Lines(0)).err().unwrap();
+        assert!(matches!(err, TextkitError::InvalidArgument(_)));
+
+        let err = split_text("a", &SplitMode::Bytes(0)).err().unwrap();
+        assert!(matches!(err, TextkitError::InvalidArgument(_)));
+    }
+
+    #[test]
+    fn parse_size_accepts_binary_suffixes() {
+        assert_eq!(512, parse_size("512").unwrap());
+        assert_eq!(2048, parse_size("2K").unwrap());
+        assert_eq!(3 << 20, parse_size("3m").unwrap());
+        assert!(parse_size("lots").is_err());
+        assert!(parse_size("").is_err());
+    }
+}
@@ -1,24 +1,206 @@
 use crate::errors::TextkitError;
+use text_stats::lines::{LinesWithEndings, lines};
 
 pub struct TextStats {
     pub lines: usize,
     pub words: usize,
     pub chars: usize,
     pub bytes: usize,
+    pub line_endings: LineEndings,
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct LineEndings {
+    pub lf: usize,
+    pub crlf: usize,
+    pub cr: usize,
+}
+
+impl LineEndings {
+    /// Name of the most common line ending, or `"none"` for single-line text.
+    pub fn style(&self) -> &'static str {
+        if self.total() == 0 {
+            "none"
+        } else if self.crlf >= self.lf && self.crlf >= self.cr {
+            "CRLF"
+        } else if self.lf >= self.cr {
+            "LF"
+        } else {
+            "CR"
+        }
+    }
+
+    /// Number of line endings that differ from the dominant style.
+    pub fn mixed(&self) -> usize {
+        self.total() - self.lf.max(self.crlf).max(self.cr)
+    }
+
+    fn total(&self) -> usize {
+        self.lf + self.crlf + self.cr
+    }
 }
 
 pub fn analyze(text: &str) -> Result<TextStats, TextkitError> {
     Ok(TextStats {
-        lines: text.lines().count(),
+        lines: lines(text).count(),
         words: text.split_whitespace().count(),
         chars: text.chars().count(),
         bytes: text.len(),
+        line_endings: line_endings(text),
+    })
+}
+
+pub fn line_endings(text: &str) -> LineEndings {
+    let mut out = LineEndings::default();
+    for (_, ending) in LinesWithEndings::new(text) {
+        match ending {
+            "\n" => out.lf += 1,
+            "\r\n" => out.crlf += 1,
+            "\r" => out.cr += 1,
+            _ => {}
+        }
+    }
+    out
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Distribution {
+    pub count: usize,
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+    pub p50: f64,
+    pub p90: f64,
+    pub p95: f64,
+    pub p99: f64,
+}
+
+/// Summarizes `values` with nearest-rank percentiles; `None` when empty.
+pub fn distribution(values: &[f64]) -> Option<Distribution> {
+    if values.is_empty() {
+        return None;
+    }
+
+    let mut sorted = values.to_vec();
+    sorted.sort_by(f64::total_cmp);
+
+    let percentile = |p: f64| {
+        let rank = (p / 100.0 * sorted.len() as f64).ceil() as usize;
+        sorted[rank.clamp(1, sorted.len()) - 1]
+    };
+
+    Some(Distribution {
+        count: sorted.len(),
+        min: sorted[0],
+        max: sorted[sorted.len() - 1],
+        mean: sorted.iter().sum::<f64>() / sorted.len() as f64,
+        p50: percentile(50.0),
+        p90: percentile(90.0),
+        p95: percentile(95.0),
+        p99: percentile(99.0),
     })
 }
 
+/// Length of every line in chars (Unicode scalar values).
+pub fn line_lengths(text: &str) -> Vec<f64> {
+    lines(text).map(|l| l.chars().count() as f64).collect()
+}
+
+pub struct ColumnValues {
+    pub values: Vec<f64>,
+    /// Lines where the field is missing or not a number.
+    pub skipped: usize,
+}
+
+/// Parses the 1-based `field` of every line as a number. Fields are split
+/// on `delimiter`, or on runs of whitespace when it is `None`.
+pub fn column_values(
+    text: &str,
+    field: usize,
+    delimiter: Option<char>,
+) -> Result<ColumnValues, TextkitError> {
+    if field == 0 {
+        return Err(TextkitError::InvalidArgument(
+            "field index starts at 1".to_string(),
+        ));
+    }
+
+    let mut out = ColumnValues {
+        values: Vec::new(),
+        skipped: 0,
+    };
+
+    for line in lines(text) {
+        let value = match delimiter {
+            Some(d) => line.split(d).nth(field - 1),
+            None => line.split_whitespace().nth(field - 1),
+        }
+        .and_then(|v| v.trim().parse::<f64>().ok())
+        .filter(|v| v.is_finite());
+
+        match value {
+            Some(v) => out.values.push(v),
+            None => out.skipped += 1,
+        }
+    }
+
+    Ok(out)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bucket {
+    pub lower: f64,
+    pub upper: f64,
+    pub count: usize,
+}
+
+/// Splits the range of `values` into `buckets` equal-width buckets. The last
+/// bucket includes its upper bound.
+pub fn histogram(values: &[f64], buckets: usize) -> Result<Vec<Bucket>, TextkitError> {
+    if buckets == 0 {
+        return Err(TextkitError::InvalidArgument(
+            "bucket count must be greater than zero".to_string(),
+        ));
+    }
+
+    let Some(d) = distribution(values) else {
+        return Ok(Vec::new());
+    };
+
+    // a single distinct value gets a single bucket instead of zero-width ones
+    let buckets = if d.min == d.max { 1 } else { buckets };
+    let width = (d.max - d.min) / buckets as f64;
+
+    let mut out: Vec<Bucket> = (0..buckets)
+        .map(|i| Bucket {
+            lower: d.min + width * i as f64,
+            upper: if i + 1 == buckets {
+                d.max
+            } else {
+                d.min + width * (i + 1) as f64
+            },
+            count: 0,
+        })
+        .collect();
+
+    for v in values {
+        let i = if width == 0.0 {
+            0
+        } else {
+            (((v - d.min) / width) as usize).min(buckets - 1)
+        };
+        out[i].count += 1;
+    }
+
+    Ok(out)
+}
+
 #[cfg(test)]
 mod tests {
-    use super::analyze;
+    use super::{
+        LineEndings, analyze, column_values, distribution, histogram, line_endings, line_lengths,
+    };
+    use crate::errors::TextkitError;
 
     #[test]
     fn stats_empty_text_is_zeroes() {
@@ -45,4 +227,96 @@ mod tests {
         assert_eq!(2, s.lines); // two lines: "  one\t two" and "three  "
         assert_eq!(3, s.words);
     }
+
+    #[test]
+    fn line_endings_counts_each_style() {
+        let e = line_endings("a\r\nb\r\nc\nd\re");
+        assert_eq!(
+            LineEndings {
+                lf: 1,
+                crlf: 2,
+                cr: 1
+            },
+            e
+        );
+        assert_eq!("CRLF", e.style());
+        assert_eq!(2, e.mixed());
+    }
+
+    #[test]
+    fn lines_agree_with_line_endings() {
+        let text = "a\r\nb\rc\n";
+        let e = line_endings(text);
+        assert_eq!(3, analyze(text).unwrap().lines);
+        assert_eq!(3, e.lf + e.crlf + e.cr);
+        assert_eq!(vec![1.0, 1.0, 1.0], line_lengths(text));
+    }
+
+    #[test]
+    fn line_endings_without_breaks_is_none() {
+        let e = line_endings("single line");
+        assert_eq!("none", e.style());
+        assert_eq!(0, e.mixed());
+    }
+
+    #[test]
+    fn distribution_uses_nearest_rank_percentiles() {
+        let values: Vec<f64> = (1..=100).map(f64::from).collect();
+        let d = distribution(&values).unwrap();
+
+        assert_eq!(100, d.count);
+        assert_eq!(1.0, d.min);
+        assert_eq!(100.0, d.max);
+        assert_eq!(50.5, d.mean);
+        assert_eq!(50.0, d.p50);
+        assert_eq!(90.0, d.p90);
+        assert_eq!(95.0, d.p95);
+        assert_eq!(99.0, d.p99);
+    }
+
+    #[test]
+    fn distribution_of_nothing_is_none() {
+        assert!(distribution(&[]).is_none());
+    }
+
+    #[test]
+    fn line_lengths_count_chars() {
+        assert_eq!(vec![3.0, 0.0, 2.0], line_lengths("abc\n\néé\n"));
+    }
+
+    #[test]
+    fn column_values_skips_missing_and_non_numeric() {
+        let text = "a 1.5\nb x\nc\nd 4\n";
+        let col = column_values(text, 2, None).unwrap();
+        assert_eq!(vec![1.5, 4.0], col.values);
+        assert_eq!(2, col.skipped);
+
+        let csv = column_values("1,10\n2,20\n", 2, Some(',')).unwrap();
+        assert_eq!(vec![10.0, 20.0], csv.values);
+
+        let err = column_values(text, 0, None).err().unwrap();
+        assert!(matches!(err, TextkitError::InvalidArgument(_)));
+    }
+
+    #[test]
+    fn histogram_buckets_cover_the_whole_range() {
+        let buckets = histogram(&[0.0, 1.0, 2.0, 3.0, 4.0], 2).unwrap();
+
+        assert_eq!(2, buckets.len());
+        assert_eq!(
+            (0.0, 2.0, 2),
+            (buckets[0].lower, buckets[0].upper, buckets[0].count)
+        );
+        assert_eq!(
+            (2.0, 4.0, 3),
+            (buckets[1].lower, buckets[1].upper, buckets[1].count)
+        );
+    }
+
+    #[test]
+    fn histogram_of_constant_values_has_one_bucket() {
+        let buckets = histogram(&[7.0, 7.0], 10).unwrap();
+        assert_eq!(1, buckets.len());
+        assert_eq!(2, buckets[0].count);
+    }
 }
@@ -0,0 +1,142 @@
+use crate::errors::TextkitError;
+use std::fmt::{self, Write};
+
+/// Placeholders available to `grep --template`, in the order their values are
+/// passed to [`Template::render`].
+pub const GREP_FIELDS: &[&str] = &["file", "line", "col", "text"];
+/// Placeholders available to `uniq --template`.
+pub const UNIQ_FIELDS: &[&str] = &["count", "text"];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Literal(String),
+    /// Index into the field list the template was parsed with.
+    Field(usize),
+}
+
+/// An output line format such as `{file}:{line}:{col} {text}`.
+///
+/// `{{` and `}}` produce literal braces; `\t`, `\n` and `\\` produce a tab, a
+/// newline and a backslash.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Template {
+    segments: Vec<Segment>,
+}
+
+impl Template {
+    /// Parses `source`, accepting only the placeholders named in `fields`.
+    pub fn parse(source: &str, fields: &[&str]) -> Result<Self, TextkitError> {
+        let mut segments = Vec::new();
+        let mut literal = String::new();
+        let mut chars = source.chars();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => match chars.next() {
+                    Some('t') => literal.push('\t'),
+                    Some('n') => literal.push('\n'),
+                    Some('\\') => literal.push('\\'),
+                    Some(other) => return Err(invalid(format!("unknown escape: \\{other}"))),
+                    None => return Err(invalid("trailing backslash".to_string())),
+                },
+                '{' if chars.as_str().starts_with('{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.as_str().starts_with('}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    let rest = chars.as_str();
+                    let Some(end) = rest.find('}') else {
+                        return Err(invalid("unclosed placeholder".to_string()));
+                    };
+                    let name = &rest[..end];
+                    let Some(index) = fields.iter().position(|f| *f == name) else {
+                        return Err(invalid(format!(
+                            "unknown placeholder: {{{name}}} (expected one of {})",
+                            fields
+                                .iter()
+                                .map(|f| format!("{{{f}}}"))
+                                .collect::<Vec<_>>()
+                                .join(", ")
+                        )));
+                    };
+                    if !literal.is_empty() {
+                        segments.push(Segment::Literal(std::mem::take(&mut literal)));
+                    }
+                    segments.push(Segment::Field(index));
+                    chars = rest[end + 1..].chars();
+                }
+                '}' => {
+                    return Err(invalid(
+                        "unmatched `}` (use `}}` for a literal brace)".to_string(),
+                    ));
+                }
+                c => literal.push(c),
+            }
+        }
+
+        if !literal.is_empty() {
+            segments.push(Segment::Literal(literal));
+        }
+
+        Ok(Template { segments })
+    }
+
+    /// Renders one output line; `values` follow the order of the field list
+    /// given to [`Template::parse`].
+    pub fn render(&self, values: &[&dyn fmt::Display]) -> String {
+        let mut out = String::new();
+        for segment in &self.segments {
+            match segment {
+                Segment::Literal(s) => out.push_str(s),
+                Segment::Field(i) => {
+                    // writing to a String cannot fail
+                    let _ = write!(out, "{}", values[*i]);
+                }
+            }
+        }
+        out
+    }
+}
+
+fn invalid(msg: String) -> TextkitError {
+    TextkitError::InvalidArgument(format!("template: {msg}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{GREP_FIELDS, Template, UNIQ_FIELDS};
+    use crate::errors::TextkitError;
+
+    #[test]
+    fn renders_quickfix_format() {
+        let t = Template::parse("{file}:{line}:{col} {text}", GREP_FIELDS).unwrap();
+        assert_eq!(
+            "app.log:12:5 boom",
+            t.render(&[&"app.log", &12, &5, &"boom"])
+        );
+    }
+
+    #[test]
+    fn handles_escapes_and_literal_braces() {
+        let t = Template::parse("{count}\\t{{{text}}}\\\\\\n", UNIQ_FIELDS).unwrap();
+        assert_eq!("3\t{a}\\\n", t.render(&[&3, &"a"]));
+    }
+
+    #[test]
+    fn placeholders_may_repeat_or_be_omitted() {
+        let t = Template::parse("{text}{text}", UNIQ_FIELDS).unwrap();
+        assert_eq!("abab", t.render(&[&1, &"ab"]));
+    }
+
+    #[test]
+    fn rejects_unknown_placeholders_and_bad_syntax() {
+        for source in ["{count}", "{text", "text}", "\\q", "end\\"] {
+            let err = Template::parse(source, GREP_FIELDS).err().unwrap();
+            assert!(matches!(err, TextkitError::InvalidArgument(_)), "{source}");
+        }
+    }
+}
@@ -1,5 +1,5 @@
 use crate::errors::TextkitError;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 pub fn uniq_lines(text: &str, all: bool) -> Result<Vec<String>, TextkitError> {
     let mut out: Vec<String> = Vec::new();
@@ -28,9 +28,54 @@ pub fn uniq_lines(text: &str, all: bool) -> Result<Vec<String>, TextkitError> {
     Ok(out)
 }
 
+/// A distinct line and how many times it occurred.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UniqLine<'a> {
+    pub text: &'a str,
+    pub count: usize,
+}
+
+/// Like [`uniq_lines`], but also counts the occurrences folded into each line:
+/// consecutive repeats by default, or every occurrence in the file when `all`
+/// is set.
+pub fn uniq_counts(text: &str, all: bool) -> Result<Vec<UniqLine<'_>>, TextkitError> {
+    let mut out: Vec<UniqLine<'_>> = Vec::new();
+
+    if all {
+        let mut index: HashMap<&str, usize> = HashMap::new();
+
+        for line in text.lines() {
+            match index.get(line) {
+                Some(&i) => out[i].count += 1,
+                None => {
+                    index.insert(line, out.len());
+                    out.push(UniqLine {
+                        text: line,
+                        count: 1,
+                    });
+                }
+            }
+        }
+
+        return Ok(out);
+    }
+
+    for line in text.lines() {
+        match out.last_mut() {
+            Some(last) if last.text == line => last.count += 1,
+            _ => out.push(UniqLine {
+                text: line,
+                count: 1,
+            }),
+        }
+    }
+
+    Ok(out)
+}
+
 #[cfg(test)]
 mod tests {
-    use super::uniq_lines;
+    use super::{UniqLine, uniq_counts, uniq_lines};
 
     #[test]
     fn uniq_all_keeps_first_occurrence_of_each_line() {
@@ -55,4 +100,19 @@ mod tests {
 
         assert_eq!(expected, result);
     }
+
+    #[test]
+    fn uniq_counts_adjacent_and_all() {
+        let input = "a\na\nb\na\n";
+        let counts = |all| {
+            uniq_counts(input, all)
+                .unwrap()
+                .into_iter()
+                .map(|UniqLine { text, count }| (text, count))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(vec![("a", 2), ("b", 1), ("a", 1)], counts(false));
+        assert_eq!(vec![("a", 3), ("b", 1)], counts(true));
+    }
 }
@@ -28,3 +28,548 @@ fn grep_on_sample_file() {
     cmd.args(["grep", "First", "-i", "-n", "tests/fixtures/sample.txt"]);
     cmd.assert().success().stdout(contains("1:First line"));
 }
+
+#[test]
+fn stats_reports_detected_cp1251_encoding() {
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("textkit"));
+
+    cmd.args(["stats", "tests/fixtures/cp1251.txt"]);
+    cmd.assert()
+        .success()
+        .stdout(contains("lines: 2\nwords: 4"))
+        .stdout(contains("encoding: windows-1251\ninvalid sequences: 0"));
+}
+
+#[test]
+fn grep_reads_utf16_with_bom() {
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("textkit"));
+
+    cmd.args(["grep", "-n", "Second", "tests/fixtures/utf16le_bom.txt"]);
+    cmd.assert().success().stdout(contains("2:Second line"));
+}
+
+#[test]
+fn strict_encoding_rejects_invalid_bytes() {
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("textkit"));
+
+    cmd.args([
+        "stats",
+        "--encoding",
+        "utf-8",
+        "tests/fixtures/invalid_utf8.txt",
+    ]);
+    cmd.assert()
+        .failure()
+        .stderr(contains("invalid UTF-8 sequence at byte 14"));
+}
+
+#[test]
+fn missing_file_exits_with_failure() {
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("textkit"));
+
+    cmd.args(["stats", "tests/fixtures/no_such_file.txt"]);
+    cmd.assert().code(1).stdout("").stderr(contains("Error: "));
+}
+
+#[test]
+fn lossy_stats_counts_invalid_sequences() {
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("textkit"));
+
+    cmd.args([
+        "stats",
+        "--encoding",
+        "utf-8",
+        "--lossy",
+        "tests/fixtures/invalid_utf8.txt",
+    ]);
+    cmd.assert()
+        .success()
+        .stdout(contains("encoding: UTF-8\ninvalid sequences: 2"));
+}
+
+#[test]
+fn stats_reads_compressed_files() {
+    for path in [
+        "tests/fixtures/sample.txt.gz",
+        "tests/fixtures/sample.txt.zst",
+        "tests/fixtures/sample.txt.bz2",
+    ] {
+        let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("textkit"));
+
+        cmd.args(["stats", path]);
+        cmd.assert()
+            .success()
+            .stdout(contains("lines: 4\nwords: 8\nchars: 46\nbytes: 46"));
+    }
+}
+
+#[test]
+fn compression_is_detected_by_content_not_extension() {
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("textkit"));
+
+    cmd.args(["uniq", "tests/fixtures/rotated.log.1"]);
+    cmd.assert()
+        .success()
+        .stdout(contains("First line\nSecond line\nThird line"));
+}
+
+#[test]
+fn stats_reports_line_ending_style() {
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("textkit"));
+
+    cmd.args(["stats", "tests/fixtures/crlf_mixed.txt"]);
+    cmd.assert()
+        .success()
+        .stdout(contains("line endings: CRLF\nmixed endings: 1"));
+}
+
+#[test]
+fn normalize_converts_endings_and_whitespace() {
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("textkit"));
+
+    cmd.args([
+        "normalize",
+        "--line-ending",
+        "lf",
+        "--strip-trailing",
+        "--tabs-to-spaces",
+        "4",
+        "tests/fixtures/crlf_mixed.txt",
+    ]);
+    cmd.assert()
+        .success()
+        .stdout("alpha\nbeta\n    gamma\ndelta\n");
+}
+
+#[test]
+fn normalize_keeps_bom_unless_stripped() {
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("textkit"));
+    cmd.args(["normalize", "tests/fixtures/utf16le_bom.txt"]);
+    cmd.assert()
+        .success()
+        .stdout("\u{FEFF}First line\nSecond line\n");
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("textkit"));
+    cmd.args(["normalize", "--strip-bom", "tests/fixtures/utf16le_bom.txt"]);
+    cmd.assert().success().stdout("First line\nSecond line\n");
+}
+
+#[test]
+fn completions_for_bash() {
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("textkit"));
+
+    cmd.args(["completions", "bash"]);
+    cmd.assert()
+        .success()
+        .stdout(contains("_textkit()"))
+        .stdout(contains("--line-number"));
+}
+
+#[test]
+fn man_prints_a_page_per_subcommand() {
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("textkit"));
+
+    cmd.args(["man"]);
+    cmd.assert()
+        .success()
+        .stdout(contains(".TH textkit 1"))
+        .stdout(contains(".TH textkit-grep 1"))
+        .stdout(contains(".TH textkit-normalize 1"));
+}
+
+const CONFIG: &str = r#"
+[grep]
+line_number = true
+
+[grep.presets.errors]
+ignore_case = true
+
+[uniq]
+all = true
+"#;
+
+fn textkit_in(dir: &std::path::Path) -> Command {
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("textkit"));
+    cmd.current_dir(dir)
+        .env("XDG_CONFIG_HOME", dir.join("xdg"))
+        .env("HOME", dir);
+    cmd
+}
+
+#[test]
+fn grep_uses_config_defaults_and_presets() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("textkit.toml"), CONFIG).unwrap();
+    std::fs::write(dir.path().join("log.txt"), "ok\nERROR one\nerror two\n").unwrap();
+
+    textkit_in(dir.path())
+        .args(["grep", "error", "log.txt"])
+        .assert()
+        .success()
+        .stdout("3:error two\n");
+
+    textkit_in(dir.path())
+        .args(["grep", "--preset", "errors", "error", "log.txt"])
+        .assert()
+        .success()
+        .stdout("2:ERROR one\n3:error two\n");
+
+    textkit_in(dir.path())
+        .args([
+            "grep",
+            "--preset",
+            "errors",
+            "--no-line-number",
+            "error",
+            "log.txt",
+        ])
+        .assert()
+        .success()
+        .stdout("ERROR one\nerror two\n");
+}
+
+#[test]
+fn config_is_found_under_xdg_config_home() {
+    let dir = tempfile::tempdir().unwrap();
+    let xdg = dir.path().join("xdg/textkit");
+    std::fs::create_dir_all(&xdg).unwrap();
+    std::fs::write(xdg.join("textkit.toml"), CONFIG).unwrap();
+    std::fs::write(dir.path().join("log.txt"), "a\nb\na\n").unwrap();
+
+    textkit_in(dir.path())
+        .args(["uniq", "log.txt"])
+        .assert()
+        .success()
+        .stdout("a\nb\n");
+
+    textkit_in(dir.path())
+        .args(["uniq", "--no-all", "log.txt"])
+        .assert()
+        .success()
+        .stdout("a\nb\na\n");
+}
+
+#[test]
+fn config_show_prints_effective_settings() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("textkit.toml"), CONFIG).unwrap();
+
+    textkit_in(dir.path())
+        .args(["config", "show"])
+        .assert()
+        .success()
+        .stdout(contains("# config file: "))
+        .stdout(contains("encoding = \"auto\""))
+        .stdout(contains("[grep]\nline_number = true\nignore_case = false"))
+        .stdout(contains("[grep.presets.errors]\nignore_case = true"));
+}
+
+#[test]
+fn broken_config_only_breaks_commands_that_use_it() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("textkit.toml"), "[grep\n").unwrap();
+    std::fs::write(dir.path().join("log.txt"), "a\n").unwrap();
+
+    textkit_in(dir.path())
+        .args(["completions", "bash"])
+        .assert()
+        .success();
+    textkit_in(dir.path()).arg("man").assert().success();
+    textkit_in(dir.path())
+        .args(["stats", "log.txt"])
+        .assert()
+        .failure()
+        .stderr(contains("textkit.toml"));
+}
+
+#[test]
+fn no_lossy_overrides_config() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("textkit.toml"), "lossy = true\n").unwrap();
+    std::fs::write(dir.path().join("bad.txt"), b"ok \xff\n").unwrap();
+
+    textkit_in(dir.path())
+        .args(["stats", "--encoding", "utf-8", "bad.txt"])
+        .assert()
+        .success()
+        .stdout(contains("invalid sequences: 1"));
+    textkit_in(dir.path())
+        .args(["stats", "--encoding", "utf-8", "--no-lossy", "bad.txt"])
+        .assert()
+        .failure()
+        .stderr(contains("invalid UTF-8"));
+}
+
+#[test]
+fn unknown_preset_fails() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("textkit.toml"), CONFIG).unwrap();
+    std::fs::write(dir.path().join("log.txt"), "a\n").unwrap();
+
+    textkit_in(dir.path())
+        .args(["grep", "--preset", "missing", "a", "log.txt"])
+        .assert()
+        .failure()
+        .stderr(contains("unknown grep preset: missing"));
+}
+
+#[test]
+fn histogram_reports_line_length_distribution() {
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("textkit"));
+
+    cmd.args([
+        "histogram",
+        "--buckets",
+        "2",
+        "--width",
+        "3",
+        "tests/fixtures/sample.txt",
+    ]);
+    cmd.assert()
+        .success()
+        .stdout(contains("count: 4\nmin: 10\nmax: 11\nmean: 10.50"))
+        .stdout(contains("p50: 10\np90: 11"))
+        .stdout(contains("[10.00, 10.50] | ### 2"));
+}
+
+#[test]
+fn histogram_of_numeric_field() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("latency.csv");
+    std::fs::write(&path, "GET,12\nPOST,30\nGET,n/a\nGET,18\n").unwrap();
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("textkit"));
+    cmd.args(["histogram", "-f", "2", "-d", ",", "--width", "4"])
+        .arg(&path);
+    cmd.assert().success().stdout(contains(
+        "skipped: 1\ncount: 3\nmin: 12\nmax: 30\nmean: 20.00",
+    ));
+}
+
+#[test]
+fn split_by_lines_writes_parts_and_manifest() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("in.txt"), "a\nb\nc\nd\ne\n").unwrap();
+
+    textkit_in(dir.path())
+        .args(["split", "--lines", "2", "--prefix", "part-", "in.txt"])
+        .assert()
+        .success();
+
+    let read = |name: &str| std::fs::read_to_string(dir.path().join(name)).unwrap();
+    assert_eq!("a\nb\n", read("part-00"));
+    assert_eq!("c\nd\n", read("part-01"));
+    assert_eq!("e\n", read("part-02"));
+    assert_eq!(
+        "file\tfirst_line\tlast_line\tbytes\npart-00\t1\t2\t4\npart-01\t3\t4\t4\npart-02\t5\t5\t2\n",
+        read("part-manifest.tsv")
+    );
+}
+
+#[test]
+fn split_at_pattern() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("in.txt"), "intro\n## one\nx\n## two\ny\n").unwrap();
+
+    textkit_in(dir.path())
+        .args([
+            "split",
+            "--pattern",
+            "## ",
+            "--manifest",
+            "index.tsv",
+            "in.txt",
+        ])
+        .assert()
+        .success();
+
+    assert_eq!(
+        "## two\ny\n",
+        std::fs::read_to_string(dir.path().join("x02")).unwrap()
+    );
+    assert!(
+        std::fs::read_to_string(dir.path().join("index.tsv"))
+            .unwrap()
+            .contains("x01\t2\t3\t",)
+    );
+}
+
+#[test]
+fn split_requires_a_mode() {
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("textkit"));
+
+    cmd.args(["split", "tests/fixtures/sample.txt"]);
+    cmd.assert().failure().stderr(contains(
+        "the following required arguments were not provided",
+    ));
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("textkit"));
+    cmd.args(["split", "-l", "1", "-b", "1K", "tests/fixtures/sample.txt"]);
+    cmd.assert()
+        .failure()
+        .stderr(contains("cannot be used with"));
+}
+
+fn binary_fixture(dir: &std::path::Path) {
+    std::fs::write(dir.join("blob.bin"), b"\x7FELF\x00\x00 needle\n\x00tail\n").unwrap();
+}
+
+#[test]
+fn grep_summarizes_matches_in_binary_files() {
+    let dir = tempfile::tempdir().unwrap();
+    binary_fixture(dir.path());
+
+    textkit_in(dir.path())
+        .args(["grep", "needle", "blob.bin"])
+        .assert()
+        .success()
+        .stdout("Binary file blob.bin matches\n");
+
+    textkit_in(dir.path())
+        .args(["grep", "absent", "blob.bin"])
+        .assert()
+        .success()
+        .stdout("");
+}
+
+#[test]
+fn binary_files_modes() {
+    let dir = tempfile::tempdir().unwrap();
+    binary_fixture(dir.path());
+
+    textkit_in(dir.path())
+        .args([
+            "--binary-files",
+            "without-match",
+            "grep",
+            "needle",
+            "blob.bin",
+        ])
+        .assert()
+        .success()
+        .stdout("")
+        .stderr("");
+
+    textkit_in(dir.path())
+        .args(["--binary-files", "skip", "grep", "needle", "blob.bin"])
+        .assert()
+        .success()
+        .stdout("")
+        .stderr(contains("skipping binary file blob.bin"));
+
+    textkit_in(dir.path())
+        .args(["--binary-files", "text", "grep", "needle", "blob.bin"])
+        .assert()
+        .success()
+        .stdout(contains(" needle"));
+}
+
+#[test]
+fn stats_refuses_binary_input() {
+    let dir = tempfile::tempdir().unwrap();
+    binary_fixture(dir.path());
+
+    textkit_in(dir.path())
+        .args(["stats", "blob.bin"])
+        .assert()
+        .failure()
+        .stderr(contains("Binary file blob.bin is not text"));
+
+    textkit_in(dir.path())
+        .args(["--binary-files", "text", "stats", "blob.bin"])
+        .assert()
+        .success()
+        .stdout(contains("lines: 2"));
+}
+
+#[test]
+fn grep_template_prints_quickfix_lines() {
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("textkit"));
+
+    cmd.args([
+        "grep",
+        "-i",
+        "--template",
+        "{file}:{line}:{col}\\t{text}",
+        "line",
+        "tests/fixtures/sample.txt",
+    ]);
+    cmd.assert()
+        .success()
+        .stdout(contains("tests/fixtures/sample.txt:1:7\tFirst line\n"))
+        .stdout(contains("tests/fixtures/sample.txt:4:7\tThird line\n"));
+}
+
+#[test]
+fn uniq_template_prints_counts() {
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("textkit"));
+
+    cmd.args([
+        "uniq",
+        "--template",
+        "{count} {text}",
+        "tests/fixtures/sample.txt",
+    ]);
+    cmd.assert()
+        .success()
+        .stdout("1 First line\n2 Second line\n1 Third line\n");
+}
+
+#[test]
+fn unknown_template_placeholder_fails() {
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("textkit"));
+
+    cmd.args([
+        "uniq",
+        "--template",
+        "{line} {text}",
+        "tests/fixtures/sample.txt",
+    ]);
+    cmd.assert()
+        .failure()
+        .stderr(contains("unknown placeholder: {line}"));
+}
+
+#[test]
+fn grep_color_always_highlights_matches_and_line_numbers() {
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("textkit"));
+
+    cmd.args([
+        "grep",
+        "-n",
+        "--color=always",
+        "Third",
+        "tests/fixtures/sample.txt",
+    ]);
+    cmd.assert()
+        .success()
+        .stdout("\x1b[32m4\x1b[0m\x1b[36m:\x1b[0m\x1b[01;31mThird\x1b[0m line\n");
+}
+
+#[test]
+fn grep_bare_color_flag_means_always() {
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("textkit"));
+
+    cmd.args(["grep", "--color", "Third", "tests/fixtures/sample.txt"])
+        .env("NO_COLOR", "1");
+    cmd.assert()
+        .success()
+        .stdout("\x1b[01;31mThird\x1b[0m line\n");
+}
+
+#[test]
+fn grep_color_is_off_when_not_a_terminal() {
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("textkit"));
+
+    cmd.args(["grep", "-n", "Third", "tests/fixtures/sample.txt"]);
+    cmd.assert().success().stdout("4:Third line\n");
+
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("textkit"));
+    cmd.args([
+        "grep",
+        "--color=never",
+        "Third",
+        "tests/fixtures/sample.txt",
+    ]);
+    cmd.assert().success().stdout("Third line\n");
+}